@@ -1,89 +1,150 @@
+mod csv;
+mod dtype;
+mod index;
 mod macros;
+mod npy;
 mod ops;
 mod utils;
 
+use crate::error::{DezeroError, Result};
+pub use csv::{CsvOptions, Missing};
+pub use dtype::{Element, Float, Numeric};
+pub use index::Index;
+pub use npy::{load_npz, save_npz, NpyArray, NpyElement};
 use rand::{distributions::Standard, Rng};
 use rand_distr::{Distribution, Normal};
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display, rc::Rc};
+use utils::{contiguous_strides, Elements, Offsets};
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Array {
-    data: Vec<f32>,
+#[derive(Clone)]
+pub struct Array<T = f32> {
+    data: Rc<Vec<T>>,
     shape: Vec<usize>,
+    strides: Vec<usize>,
+    offset: usize,
     size: usize,
 }
 
-impl Array {
-    pub fn new(data: Vec<f32>, shape: Vec<usize>) -> Array {
+impl<T: Element> Array<T> {
+    pub fn new(data: Vec<T>, shape: Vec<usize>) -> Array<T> {
+        Array::try_new(data, shape).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(data: Vec<T>, shape: Vec<usize>) -> Result<Array<T>> {
         let size = data.len();
         if size != shape.iter().product() {
-            panic!("The data and the shape are inconsistent")
+            return Err(DezeroError::ShapeMismatch {
+                op: "new",
+                lhs: vec![size],
+                rhs: shape,
+            });
         }
-        let size = data.len();
-        Array { data, shape, size }
+        let strides = contiguous_strides(&shape);
+        Ok(Array {
+            data: Rc::new(data),
+            shape,
+            strides,
+            offset: 0,
+            size,
+        })
+    }
+
+    pub fn full(shape: &[usize], value: T) -> Array<T> {
+        let size = shape.iter().product();
+        Array::new(vec![value; size], shape.to_vec())
     }
 
-    pub fn read_csv(path: &str) -> Array {
-        let f = std::fs::read_to_string(path).expect("File not found");
-        let lines = f.lines().collect::<Vec<_>>();
-        let num_rows = lines.len();
-        let num_cols = lines[0].split(',').count();
-        let data = lines
+    fn view(&self, shape: Vec<usize>, strides: Vec<usize>, offset: usize) -> Array<T> {
+        let size = shape.iter().product();
+        Array {
+            data: self.data.clone(),
+            shape,
+            strides,
+            offset,
+            size,
+        }
+    }
+
+    pub fn get_data(&self) -> Cow<'_, [T]> {
+        match self.as_slice() {
+            Some(data) => Cow::Borrowed(data),
+            None => Cow::Owned(self.to_vec()),
+        }
+    }
+
+    pub fn get_shape(&self) -> &Vec<usize> {
+        &self.shape
+    }
+
+    pub fn get_strides(&self) -> &Vec<usize> {
+        &self.strides
+    }
+
+    pub fn set_data(&mut self, new: Array<T>) {
+        *self = new.reshape(&self.shape);
+    }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.shape
             .iter()
-            .flat_map(|s| s.split(',').map(|d| d.parse::<f32>().unwrap()))
-            .collect::<Vec<f32>>();
+            .zip(self.strides.iter().zip(contiguous_strides(&self.shape)))
+            .all(|(&n, (&stride, expected))| n == 1 || stride == expected)
+    }
 
-        Array::new(data, vec![num_rows, num_cols])
+    pub fn contiguous(&self) -> Array<T> {
+        if self.is_contiguous() {
+            self.clone()
+        } else {
+            Array::new(self.to_vec(), self.shape.clone())
+        }
     }
 
-    pub fn write_csv(&self, path: &str) {
-        if self.shape.len() != 2 {
-            panic!("This implementation is temporary and can only handle 2dim arrays.")
+    pub fn as_slice(&self) -> Option<&[T]> {
+        if self.is_contiguous() {
+            Some(&self.data[self.offset..self.offset + self.size])
+        } else {
+            None
         }
-        let string = self
-            .data
-            .chunks(self.shape[1])
-            .map(|row| {
-                row.iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>()
-                    .join(",")
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        match self.as_slice() {
+            Some(data) => Elements::Contiguous(data.iter()),
+            None => Elements::Strided(&self.data, self.offsets()),
+        }
+    }
 
-        std::fs::write(path, string).unwrap();
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
     }
 
+    fn offsets(&self) -> Offsets<'_> {
+        Offsets::new(&self.shape, &self.strides, self.offset)
+    }
+
+    pub fn to_string(&self, depth: usize) -> String {
+        array_to_string(&self.get_data(), &self.shape, depth)
+    }
+}
+
+impl Array {
     pub fn zeros(shape: &[usize]) -> Array {
         let size = shape.iter().product();
         let data = vec![0.; size];
-        Array {
-            data,
-            shape: shape.to_vec(),
-            size,
-        }
+        Array::new(data, shape.to_vec())
     }
 
     pub fn ones(shape: &[usize]) -> Array {
         let size = shape.iter().product();
         let data = vec![1.; size];
-        Array {
-            data,
-            shape: shape.to_vec(),
-            size,
-        }
+        Array::new(data, shape.to_vec())
     }
 
     pub fn rand(shape: &[usize]) -> Array {
         let size = shape.iter().product();
         let rng = rand::thread_rng();
         let data = rng.sample_iter(Standard).take(size).collect();
-        Array {
-            data,
-            shape: shape.to_vec(),
-            size,
-        }
+        Array::new(data, shape.to_vec())
     }
 
     pub fn randn(shape: &[usize], mean: f32, std_dev: f32) -> Array {
@@ -92,42 +153,33 @@ impl Array {
         let normal = Normal::new(mean, std_dev).unwrap();
         let data = normal.sample_iter(rng).take(size).collect();
 
-        Array {
-            data,
-            shape: shape.to_vec(),
-            size,
-        }
-    }
-
-    pub fn get_data(&self) -> &Vec<f32> {
-        &self.data
-    }
-
-    pub fn get_shape(&self) -> &Vec<usize> {
-        &self.shape
+        Array::new(data, shape.to_vec())
     }
+}
 
-    pub fn set_data(&mut self, new: Array) {
-        let data = &mut self.data;
-        let new_data = new.data;
-        for (old, new) in data.iter_mut().zip(new_data.into_iter()) {
-            *old = new
-        }
+impl<T: Element> PartialEq for Array<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape == other.shape && self.iter().eq(other.iter())
     }
+}
 
-    pub fn to_string(&self, depth: usize) -> String {
-        array_to_string(&self.data, &self.shape, depth)
+impl<T: Element> std::fmt::Debug for Array<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Array")
+            .field("data", &self.get_data())
+            .field("shape", &self.shape)
+            .finish()
     }
 }
 
-impl Display for Array {
+impl<T: Element> Display for Array<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let string = array_to_string(&self.data, &self.shape, 0);
+        let string = array_to_string(&self.get_data(), &self.shape, 0);
         write!(f, "{}", string)
     }
 }
 
-fn array_to_string(data: &[f32], shape: &[usize], depth: usize) -> String {
+fn array_to_string<T: Element>(data: &[T], shape: &[usize], depth: usize) -> String {
     match shape.len() {
         0 => data[0].to_string(),
         1 => format!("{:?}", data),
@@ -0,0 +1,187 @@
+use super::Array;
+use crate::error::{DezeroError, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Missing {
+    Error,
+    Fill(f32),
+    SkipRow,
+}
+
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    delimiter: char,
+    has_header: bool,
+    columns: Option<Vec<usize>>,
+    label_column: Option<usize>,
+    missing: Missing,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: false,
+            columns: None,
+            label_column: None,
+            missing: Missing::Error,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        CsvOptions::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    pub fn columns(mut self, columns: &[usize]) -> Self {
+        self.columns = Some(columns.to_vec());
+        self
+    }
+
+    pub fn label_column(mut self, column: usize) -> Self {
+        self.label_column = Some(column);
+        self
+    }
+
+    pub fn missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+}
+
+impl Array {
+    pub fn read_csv(path: &str) -> Array {
+        Array::try_read_csv(path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    pub fn try_read_csv(path: &str) -> Result<Array> {
+        Ok(Array::try_read_csv_with(path, &CsvOptions::default())?.0)
+    }
+
+    // Returns the selected data columns and, if `label_column` is set, the labels.
+    pub fn try_read_csv_with(path: &str, options: &CsvOptions) -> Result<(Array, Option<Array>)> {
+        let f = std::fs::read_to_string(path)?;
+        let mut lines = f
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        if options.has_header {
+            lines.next();
+        }
+
+        let mut num_fields = None;
+        let mut num_rows = 0;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        'rows: for (i, line) in lines {
+            let fields = line.split(options.delimiter).collect::<Vec<_>>();
+            let expected = *num_fields.get_or_insert(fields.len());
+            if fields.len() != expected {
+                return Err(DezeroError::Parse {
+                    line: i + 1,
+                    column: None,
+                    message: format!("expected {} columns, found {}", expected, fields.len()),
+                });
+            }
+
+            // The label column is never read as a feature, even if it is listed in `columns`.
+            let columns = match &options.columns {
+                Some(columns) => columns.clone(),
+                None => (0..fields.len()).collect(),
+            };
+            let columns = columns
+                .into_iter()
+                .filter(|&j| Some(j) != options.label_column)
+                .collect::<Vec<_>>();
+            let mut row = Vec::with_capacity(columns.len() + 1);
+            for j in columns.iter().copied().chain(options.label_column) {
+                let field = fields.get(j).ok_or_else(|| DezeroError::Parse {
+                    line: i + 1,
+                    column: Some(j + 1),
+                    message: format!("column {} does not exist", j + 1),
+                })?;
+                let field = field.trim();
+                let x = if field.is_empty() {
+                    match options.missing {
+                        Missing::Fill(x) if Some(j) != options.label_column => x,
+                        Missing::SkipRow => continue 'rows,
+                        _ => {
+                            return Err(DezeroError::Parse {
+                                line: i + 1,
+                                column: Some(j + 1),
+                                message: "missing value".to_string(),
+                            })
+                        }
+                    }
+                } else {
+                    field.parse::<f32>().map_err(|e| DezeroError::Parse {
+                        line: i + 1,
+                        column: Some(j + 1),
+                        message: format!("{:?}: {}", field, e),
+                    })?
+                };
+                row.push(x);
+            }
+
+            if options.label_column.is_some() {
+                labels.push(row.pop().unwrap());
+            }
+            data.append(&mut row);
+            num_rows += 1;
+        }
+
+        let num_cols = data.len().checked_div(num_rows).unwrap_or(0);
+        let labels = match options.label_column {
+            Some(_) => Some(Array::try_new(labels, vec![num_rows])?),
+            None => None,
+        };
+        Ok((Array::try_new(data, vec![num_rows, num_cols])?, labels))
+    }
+
+    pub fn write_csv(&self, path: &str) {
+        self.try_write_csv(path, &CsvOptions::default())
+            .unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    // Arrays of rank other than 2 are written with their trailing axes flattened
+    // into columns; 0-D and 1-D arrays become a single column. With `has_header`,
+    // the first row holds the column indices.
+    pub fn try_write_csv(&self, path: &str, options: &CsvOptions) -> Result<()> {
+        let num_cols = match self.shape.len() {
+            0 | 1 => 1,
+            _ => self.shape[1..].iter().product(),
+        };
+        let delimiter = options.delimiter.to_string();
+        let header = (0..num_cols)
+            .map(|j| j.to_string())
+            .collect::<Vec<String>>()
+            .join(&delimiter);
+        let data = self.get_data();
+        let string = options
+            .has_header
+            .then_some(header)
+            .into_iter()
+            .chain(data.chunks(num_cols.max(1)).map(|row| {
+                row.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(&delimiter)
+            }))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        std::fs::write(path, string)?;
+        Ok(())
+    }
+}
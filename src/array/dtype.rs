@@ -0,0 +1,148 @@
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+pub trait Element: Copy + PartialEq + Debug + Display + Send + Sync + 'static {
+    const NAME: &'static str;
+
+    fn to_f64(self) -> f64;
+    fn from_f64(x: f64) -> Self;
+}
+
+pub trait Numeric:
+    Element
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn maximum(self, other: Self) -> Self;
+    fn minimum(self, other: Self) -> Self;
+}
+
+pub trait Float: Numeric + Neg<Output = Self> {
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+}
+
+macro_rules! impl_element {
+    ($type: ty, $name: expr) => {
+        impl Element for $type {
+            const NAME: &'static str = $name;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(x: f64) -> Self {
+                x as $type
+            }
+        }
+    };
+}
+
+macro_rules! impl_numeric {
+    ($type: ty, $min: expr, $max: expr, $maximum: path, $minimum: path) => {
+        impl Numeric for $type {
+            const ZERO: Self = 0 as $type;
+            const ONE: Self = 1 as $type;
+            const MIN: Self = $min;
+            const MAX: Self = $max;
+
+            fn maximum(self, other: Self) -> Self {
+                $maximum(self, other)
+            }
+
+            fn minimum(self, other: Self) -> Self {
+                $minimum(self, other)
+            }
+        }
+    };
+}
+
+macro_rules! impl_float {
+    ($type: ident) => {
+        impl Float for $type {
+            fn exp(self) -> Self {
+                $type::exp(self)
+            }
+            fn ln(self) -> Self {
+                $type::ln(self)
+            }
+            fn sin(self) -> Self {
+                $type::sin(self)
+            }
+            fn cos(self) -> Self {
+                $type::cos(self)
+            }
+            fn tan(self) -> Self {
+                $type::tan(self)
+            }
+            fn sinh(self) -> Self {
+                $type::sinh(self)
+            }
+            fn cosh(self) -> Self {
+                $type::cosh(self)
+            }
+            fn tanh(self) -> Self {
+                $type::tanh(self)
+            }
+            fn sqrt(self) -> Self {
+                $type::sqrt(self)
+            }
+            fn abs(self) -> Self {
+                $type::abs(self)
+            }
+            fn powi(self, n: i32) -> Self {
+                $type::powi(self, n)
+            }
+            fn powf(self, n: Self) -> Self {
+                $type::powf(self, n)
+            }
+        }
+    };
+}
+
+impl_element!(f32, "f32");
+impl_element!(f64, "f64");
+impl_element!(i32, "i32");
+impl_element!(i64, "i64");
+
+impl Element for bool {
+    const NAME: &'static str = "bool";
+
+    fn to_f64(self) -> f64 {
+        if self {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    fn from_f64(x: f64) -> Self {
+        x != 0.
+    }
+}
+
+impl_numeric!(f32, f32::NEG_INFINITY, f32::INFINITY, f32::max, f32::min);
+impl_numeric!(f64, f64::NEG_INFINITY, f64::INFINITY, f64::max, f64::min);
+impl_numeric!(i32, i32::MIN, i32::MAX, Ord::max, Ord::min);
+impl_numeric!(i64, i64::MIN, i64::MAX, Ord::max, Ord::min);
+
+impl_float!(f32);
+impl_float!(f64);
@@ -0,0 +1,209 @@
+use super::{utils::*, Array, Element, Numeric};
+use std::ops::{Range, RangeFrom, RangeFull, RangeTo};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Index {
+    At(usize),
+    Slice {
+        start: usize,
+        end: Option<usize>,
+        step: usize,
+    },
+    Array(Vec<usize>),
+}
+
+impl Index {
+    pub fn full() -> Index {
+        Index::Slice {
+            start: 0,
+            end: None,
+            step: 1,
+        }
+    }
+
+    pub fn step(range: impl Into<Index>, step: usize) -> Index {
+        match range.into() {
+            Index::Slice { start, end, .. } => Index::Slice { start, end, step },
+            index => panic!("step cannot be applied to {:?}", index),
+        }
+    }
+}
+
+impl From<usize> for Index {
+    fn from(i: usize) -> Self {
+        Index::At(i)
+    }
+}
+
+impl From<Range<usize>> for Index {
+    fn from(range: Range<usize>) -> Self {
+        Index::Slice {
+            start: range.start,
+            end: Some(range.end),
+            step: 1,
+        }
+    }
+}
+
+impl From<RangeFrom<usize>> for Index {
+    fn from(range: RangeFrom<usize>) -> Self {
+        Index::Slice {
+            start: range.start,
+            end: None,
+            step: 1,
+        }
+    }
+}
+
+impl From<RangeTo<usize>> for Index {
+    fn from(range: RangeTo<usize>) -> Self {
+        Index::Slice {
+            start: 0,
+            end: Some(range.end),
+            step: 1,
+        }
+    }
+}
+
+impl From<RangeFull> for Index {
+    fn from(_: RangeFull) -> Self {
+        Index::full()
+    }
+}
+
+impl From<Vec<usize>> for Index {
+    fn from(indices: Vec<usize>) -> Self {
+        Index::Array(indices)
+    }
+}
+
+impl From<&[usize]> for Index {
+    fn from(indices: &[usize]) -> Self {
+        Index::Array(indices.to_vec())
+    }
+}
+
+struct Layout {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    offset: usize,
+}
+
+// Applies the basic (view producing) part of `indices` to a layout. An integer array index is
+// left as a full axis and returned together with its position in the resulting layout.
+fn apply_basic(mut layout: Layout, indices: &[Index]) -> (Layout, Option<(usize, Vec<usize>)>) {
+    if indices.len() > layout.shape.len() {
+        panic!(
+            "too many indices ({}) for an array of shape {:?}",
+            indices.len(),
+            layout.shape
+        )
+    }
+
+    let mut fancy = None;
+    let mut axis = 0;
+    for index in indices {
+        let n = layout.shape[axis];
+        match index {
+            &Index::At(i) => {
+                if i >= n {
+                    panic!("index {} is out of bounds for axis of size {}", i, n)
+                }
+                layout.offset += i * layout.strides[axis];
+                layout.shape.remove(axis);
+                layout.strides.remove(axis);
+            }
+            &Index::Slice { start, end, step } => {
+                let end = end.unwrap_or(n).min(n);
+                let start = start.min(end);
+                if step == 0 {
+                    panic!("slice step cannot be zero")
+                }
+                layout.shape[axis] = (end - start).div_ceil(step);
+                if layout.shape[axis] > 0 {
+                    layout.offset += start * layout.strides[axis];
+                }
+                layout.strides[axis] *= step;
+                axis += 1;
+            }
+            Index::Array(i) => {
+                if fancy.is_some() {
+                    panic!("only one integer array index is supported")
+                }
+                if let Some(&i) = i.iter().find(|&&i| i >= n) {
+                    panic!("index {} is out of bounds for axis of size {}", i, n)
+                }
+                fancy = Some((axis, i.clone()));
+                axis += 1;
+            }
+        }
+    }
+    (layout, fancy)
+}
+
+fn take<T: Copy>(data: &[T], shape: &[usize], indices: &[usize], axis: usize) -> Vec<T> {
+    let outer: usize = shape[..axis].iter().product();
+    let inner: usize = shape[axis + 1..].iter().product();
+    let mut res = Vec::with_capacity(outer * indices.len() * inner);
+    if shape[axis] * inner == 0 {
+        return res;
+    }
+    for chunk in data.chunks(shape[axis] * inner).take(outer) {
+        for &i in indices {
+            res.extend_from_slice(&chunk[i * inner..(i + 1) * inner]);
+        }
+    }
+    res
+}
+
+impl<T: Element> Array<T> {
+    pub fn slice(&self, indices: &[Index]) -> Array<T> {
+        let layout = Layout {
+            shape: self.shape.clone(),
+            strides: self.strides.clone(),
+            offset: self.offset,
+        };
+        let (layout, fancy) = apply_basic(layout, indices);
+        let view = self.view(layout.shape, layout.strides, layout.offset);
+
+        match fancy {
+            None => view,
+            Some((axis, i)) => view.take(&i, axis),
+        }
+    }
+
+    pub fn take(&self, indices: &[usize], axis: usize) -> Array<T> {
+        let mut shape = self.shape.clone();
+        let data = take(&self.get_data(), &shape, indices, axis);
+        shape[axis] = indices.len();
+        Array::new(data, shape)
+    }
+}
+
+impl<T: Numeric> Array<T> {
+    pub fn add_at(&self, indices: &[Index], values: &Array<T>) -> Array<T> {
+        let layout = Layout {
+            strides: contiguous_strides(&self.shape),
+            shape: self.shape.clone(),
+            offset: 0,
+        };
+        let (layout, fancy) = apply_basic(layout, indices);
+
+        let mut shape = layout.shape.clone();
+        let mut positions: Vec<usize> =
+            Offsets::new(&layout.shape, &layout.strides, layout.offset).collect();
+        if let Some((axis, i)) = fancy {
+            positions = take(&positions, &shape, &i, axis);
+            shape[axis] = i.len();
+        }
+
+        let mut data = self.to_vec();
+        for (position, value) in positions
+            .into_iter()
+            .zip(values.broadcast_to(&shape).iter())
+        {
+            data[position] = data[position] + value;
+        }
+        Array::new(data, self.shape.clone())
+    }
+}
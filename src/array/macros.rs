@@ -35,3 +35,10 @@ macro_rules! array_with_shape {
         )
     };
 }
+
+#[macro_export]
+macro_rules! s {
+    ($($index: expr),* $(,)?) => {
+        [$($crate::array::Index::from($index)),*]
+    };
+}
@@ -0,0 +1,438 @@
+use super::{Array, Element};
+use crate::error::{DezeroError, Result};
+use std::{collections::BTreeMap, fs, path::Path};
+
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+pub trait NpyElement: Element {
+    const DESCR: &'static str;
+    const SIZE: usize;
+
+    fn write_le(self, buf: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+    fn into_npy(array: Array<Self>) -> NpyArray;
+    // Gives the array back if it holds another dtype.
+    fn from_npy(array: NpyArray) -> std::result::Result<Array<Self>, NpyArray>;
+}
+
+// An array of any dtype that npy files can hold, e.g. one entry of an npz archive.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NpyArray {
+    F32(Array<f32>),
+    F64(Array<f64>),
+    I32(Array<i32>),
+    I64(Array<i64>),
+    Bool(Array<bool>),
+}
+
+macro_rules! impl_npy_conversions {
+    ($variant: ident) => {
+        fn into_npy(array: Array<Self>) -> NpyArray {
+            NpyArray::$variant(array)
+        }
+
+        fn from_npy(array: NpyArray) -> std::result::Result<Array<Self>, NpyArray> {
+            match array {
+                NpyArray::$variant(array) => Ok(array),
+                array => Err(array),
+            }
+        }
+    };
+}
+
+macro_rules! impl_npy_element {
+    ($type: ty, $descr: expr, $variant: ident) => {
+        impl NpyElement for $type {
+            const DESCR: &'static str = $descr;
+            const SIZE: usize = std::mem::size_of::<$type>();
+
+            impl_npy_conversions!($variant);
+
+            fn write_le(self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$type>::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    };
+}
+
+impl_npy_element!(f32, "<f4", F32);
+impl_npy_element!(f64, "<f8", F64);
+impl_npy_element!(i32, "<i4", I32);
+impl_npy_element!(i64, "<i8", I64);
+
+impl NpyElement for bool {
+    const DESCR: &'static str = "|b1";
+    const SIZE: usize = 1;
+
+    impl_npy_conversions!(Bool);
+
+    fn write_le(self, buf: &mut Vec<u8>) {
+        buf.push(self as u8);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
+
+impl<T: NpyElement> Array<T> {
+    pub fn to_npy_bytes(&self) -> Vec<u8> {
+        let shape = match self.shape.as_slice() {
+            [n] => format!("({},)", n),
+            shape => format!(
+                "({})",
+                shape
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            T::DESCR,
+            shape
+        );
+        // The data must start at a multiple of 64 bytes; the header ends with '\n'.
+        let unpadded = NPY_MAGIC.len() + 4 + header.len() + 1;
+        header += &" ".repeat((64 - unpadded % 64) % 64);
+        header += "\n";
+
+        let mut buf = Vec::with_capacity(NPY_MAGIC.len() + 4 + header.len() + self.size * T::SIZE);
+        buf.extend_from_slice(NPY_MAGIC);
+        buf.extend_from_slice(&[1, 0]);
+        buf.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        buf.extend_from_slice(header.as_bytes());
+        for x in self.iter() {
+            x.write_le(&mut buf);
+        }
+        buf
+    }
+
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<Array<T>> {
+        let (descr, shape, data) = parse_npy(bytes)?;
+        if descr != T::DESCR {
+            return Err(format_error(&format!(
+                "dtype {} cannot be read as {}",
+                descr,
+                T::NAME
+            )));
+        }
+        read_data(shape, data)
+    }
+
+    pub fn save_npy(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_npy_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_npy(path: impl AsRef<Path>) -> Result<Array<T>> {
+        Array::from_npy_bytes(&fs::read(path)?)
+    }
+}
+
+impl<T: NpyElement> From<Array<T>> for NpyArray {
+    fn from(array: Array<T>) -> Self {
+        T::into_npy(array)
+    }
+}
+
+impl NpyArray {
+    pub fn dtype(&self) -> &'static str {
+        match self {
+            NpyArray::F32(_) => f32::NAME,
+            NpyArray::F64(_) => f64::NAME,
+            NpyArray::I32(_) => i32::NAME,
+            NpyArray::I64(_) => i64::NAME,
+            NpyArray::Bool(_) => bool::NAME,
+        }
+    }
+
+    pub fn get_shape(&self) -> &Vec<usize> {
+        match self {
+            NpyArray::F32(array) => array.get_shape(),
+            NpyArray::F64(array) => array.get_shape(),
+            NpyArray::I32(array) => array.get_shape(),
+            NpyArray::I64(array) => array.get_shape(),
+            NpyArray::Bool(array) => array.get_shape(),
+        }
+    }
+
+    pub fn to_npy_bytes(&self) -> Vec<u8> {
+        match self {
+            NpyArray::F32(array) => array.to_npy_bytes(),
+            NpyArray::F64(array) => array.to_npy_bytes(),
+            NpyArray::I32(array) => array.to_npy_bytes(),
+            NpyArray::I64(array) => array.to_npy_bytes(),
+            NpyArray::Bool(array) => array.to_npy_bytes(),
+        }
+    }
+
+    // Reads the array with the dtype recorded in the header.
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<NpyArray> {
+        let (descr, shape, data) = parse_npy(bytes)?;
+        match descr {
+            <f32>::DESCR => read_data::<f32>(shape, data).map(NpyArray::F32),
+            <f64>::DESCR => read_data::<f64>(shape, data).map(NpyArray::F64),
+            <i32>::DESCR => read_data::<i32>(shape, data).map(NpyArray::I32),
+            <i64>::DESCR => read_data::<i64>(shape, data).map(NpyArray::I64),
+            <bool>::DESCR => read_data::<bool>(shape, data).map(NpyArray::Bool),
+            descr => Err(format_error(&format!("unsupported dtype {}", descr))),
+        }
+    }
+
+    pub fn into_array<T: NpyElement>(self) -> Result<Array<T>> {
+        T::from_npy(self).map_err(|array| {
+            format_error(&format!(
+                "dtype {} cannot be read as {}",
+                array.dtype(),
+                T::NAME
+            ))
+        })
+    }
+}
+
+// Entries may hold different dtypes, like the archives written by `np.savez`.
+pub fn save_npz(path: impl AsRef<Path>, arrays: &[(&str, NpyArray)]) -> Result<()> {
+    let entries = arrays
+        .iter()
+        .map(|(name, array)| (format!("{}.npy", name), array.to_npy_bytes()))
+        .collect::<Vec<_>>();
+    fs::write(path, write_zip(&entries))?;
+    Ok(())
+}
+
+pub fn load_npz(path: impl AsRef<Path>) -> Result<BTreeMap<String, NpyArray>> {
+    let bytes = fs::read(path)?;
+    read_zip(&bytes)?
+        .into_iter()
+        .map(|(name, data)| {
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            Ok((name, NpyArray::from_npy_bytes(data)?))
+        })
+        .collect()
+}
+
+// Splits an npy file into its dtype descriptor, shape and data bytes.
+fn parse_npy(bytes: &[u8]) -> Result<(&str, Vec<usize>, &[u8])> {
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC {
+        return Err(format_error("not a npy file"));
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize,
+            12,
+        ),
+        major => return Err(format_error(&format!("unsupported npy version {}", major))),
+    };
+    let data_start = header_start + header_len;
+    let header = bytes
+        .get(header_start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| format_error("invalid npy header"))?;
+
+    let descr = header_value(header, "descr")?;
+    let descr = descr
+        .strip_prefix('\'')
+        .and_then(|d| d.split('\'').next())
+        .ok_or_else(|| format_error("invalid descr"))?;
+    if !header_value(header, "fortran_order")?.starts_with("False") {
+        return Err(format_error("fortran order arrays are not supported"));
+    }
+    let shape = header_value(header, "shape")?;
+    let shape = shape
+        .strip_prefix('(')
+        .and_then(|s| s.split(')').next())
+        .ok_or_else(|| format_error("invalid shape"))?
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse::<usize>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|_| format_error("invalid shape"))?;
+    Ok((descr, shape, &bytes[data_start..]))
+}
+
+fn read_data<T: NpyElement>(shape: Vec<usize>, bytes: &[u8]) -> Result<Array<T>> {
+    let size = shape.iter().product::<usize>();
+    let data = bytes
+        .get(..size * T::SIZE)
+        .ok_or_else(|| format_error("unexpected end of npy data"))?
+        .chunks_exact(T::SIZE)
+        .map(T::read_le)
+        .collect();
+    Array::try_new(data, shape)
+}
+
+fn format_error(message: &str) -> DezeroError {
+    DezeroError::Format(message.to_string())
+}
+
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let key = format!("'{}':", key);
+    header
+        .find(&key)
+        .map(|i| header[i + key.len()..].trim_start())
+        .ok_or_else(|| format_error(&format!("npy header has no {}", key)))
+}
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIR: u32 = 0x06054b50;
+// 1980-01-01, the earliest date a zip entry can carry.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in entries {
+        let offset = buf.len() as u32;
+        let crc = crc32(data);
+
+        put_u32(&mut buf, LOCAL_HEADER);
+        put_u16(&mut buf, 20);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, DOS_DATE);
+        put_u32(&mut buf, crc);
+        put_u32(&mut buf, data.len() as u32);
+        put_u32(&mut buf, data.len() as u32);
+        put_u16(&mut buf, name.len() as u16);
+        put_u16(&mut buf, 0);
+        buf.extend_from_slice(name.as_bytes());
+        buf.extend_from_slice(data);
+
+        put_u32(&mut central, CENTRAL_HEADER);
+        put_u16(&mut central, 20);
+        put_u16(&mut central, 20);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, DOS_DATE);
+        put_u32(&mut central, crc);
+        put_u32(&mut central, data.len() as u32);
+        put_u32(&mut central, data.len() as u32);
+        put_u16(&mut central, name.len() as u16);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, 0);
+        put_u16(&mut central, 0);
+        put_u32(&mut central, 0);
+        put_u32(&mut central, offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = buf.len() as u32;
+    buf.extend_from_slice(&central);
+    put_u32(&mut buf, END_OF_CENTRAL_DIR);
+    put_u16(&mut buf, 0);
+    put_u16(&mut buf, 0);
+    put_u16(&mut buf, entries.len() as u16);
+    put_u16(&mut buf, entries.len() as u16);
+    put_u32(&mut buf, central.len() as u32);
+    put_u32(&mut buf, central_offset);
+    put_u16(&mut buf, 0);
+    buf
+}
+
+fn read_zip(bytes: &[u8]) -> Result<Vec<(String, &[u8])>> {
+    let eocd = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| get_u32(bytes, i) == Some(END_OF_CENTRAL_DIR))
+        .ok_or_else(|| format_error("not a zip archive"))?;
+    let truncated = || format_error("truncated zip archive");
+    let count = get_u16(bytes, eocd + 10).ok_or_else(truncated)?;
+    let mut pos = get_u32(bytes, eocd + 16).ok_or_else(truncated)? as usize;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if get_u32(bytes, pos) != Some(CENTRAL_HEADER) {
+            return Err(format_error("invalid zip central directory"));
+        }
+        let field = |offset| get_u16(bytes, pos + offset).ok_or_else(truncated);
+        let method = field(10)?;
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let crc = get_u32(bytes, pos + 16).ok_or_else(truncated)?;
+        let size = get_u32(bytes, pos + 20).ok_or_else(truncated)? as usize;
+        let local = get_u32(bytes, pos + 42).ok_or_else(truncated)? as usize;
+        let name = bytes
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        if method != 0 {
+            return Err(format_error(&format!(
+                "{} is compressed; only stored entries are supported",
+                name
+            )));
+        }
+
+        let start = local
+            + 30
+            + get_u16(bytes, local + 26).ok_or_else(truncated)? as usize
+            + get_u16(bytes, local + 28).ok_or_else(truncated)? as usize;
+        let data = bytes.get(start..start + size).ok_or_else(truncated)?;
+        if crc32(data) != crc {
+            return Err(format_error(&format!("crc mismatch in {}", name)));
+        }
+        entries.push((name, data));
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+fn put_u16(buf: &mut Vec<u8>, x: u16) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, x: u32) {
+    buf.extend_from_slice(&x.to_le_bytes());
+}
+
+fn get_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+fn get_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(pos..pos + 4)?.try_into().ok()?,
+    ))
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut c = !0u32;
+    for &b in data {
+        c = CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
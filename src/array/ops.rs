@@ -1,163 +1,440 @@
-use super::{utils::*, Array};
+use super::{utils::*, Array, Element, Float, Numeric};
+use crate::error::{DezeroError, Result};
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 macro_rules! define_map_functions {
     ($($fname: ident),*) => {
         $(
-        pub fn $fname(&self) -> Array {
-            self.map(f32::$fname)
+        pub fn $fname(&self) -> Array<T> {
+            self.map(T::$fname)
         })*
     };
 }
 
-impl Array {
-    pub fn map<F>(&self, f: F) -> Array
+impl<T: Element> Array<T> {
+    pub fn map<U, F>(&self, f: F) -> Array<U>
     where
-        F: Fn(f32) -> f32,
+        U: Element,
+        F: Fn(T) -> U,
     {
-        let data = self.data.iter().map(|&a| f(a)).collect();
+        let data = self.iter().map(f).collect();
         Array::new(data, self.shape.clone())
     }
 
-    define_map_functions!(exp, ln, sin, cos, tan, sinh, cosh, tanh);
+    pub fn astype<U: Element>(&self) -> Array<U> {
+        self.map(|x| U::from_f64(x.to_f64()))
+    }
 
-    pub fn powi(&self, n: i32) -> Array {
-        let data = self.data.iter().map(|a| a.powi(n)).collect();
-        Array::new(data, self.shape.clone())
+    pub fn one_hot(&self, num_classes: usize) -> Array {
+        let mut data = vec![0.; self.size * num_classes];
+        for (i, label) in self.iter().enumerate() {
+            let label = label.to_f64();
+            if label < 0. || label as usize >= num_classes {
+                panic!(
+                    "label {} is out of range for {} classes",
+                    label, num_classes
+                )
+            }
+            data[i * num_classes + label as usize] = 1.;
+        }
+        let mut shape = self.shape.clone();
+        shape.push(num_classes);
+        Array::new(data, shape)
     }
 
-    pub fn powf(&self, n: f32) -> Array {
-        let data = self.data.iter().map(|a| a.powf(n)).collect();
-        Array::new(data, self.shape.clone())
+    fn broadcast_zip<U, F>(&self, rhs: &Array<T>, f: F) -> Array<U>
+    where
+        U: Element,
+        F: Fn(T, T) -> U,
+    {
+        let shape = shape_after_broadcast(&self.shape, &rhs.shape).unwrap_or_else(|| {
+            panic!(
+                "Two arrays must have the same shape\nlhs: {:?}\nrhs: {:?}",
+                self, rhs,
+            )
+        });
+        let lhs = self.broadcast_to(&shape);
+        let rhs = rhs.broadcast_to(&shape);
+        let data = lhs.iter().zip(rhs.iter()).map(|(x, y)| f(x, y)).collect();
+        Array::new(data, shape)
     }
 
-    pub fn sum(&self) -> Array {
-        Array {
-            data: vec![self.data.iter().sum()],
-            shape: vec![],
-            size: 1,
+    pub fn reduced_shape(&self, axes: Option<&[usize]>, keepdims: bool) -> Vec<usize> {
+        let axes = self.normalize_axes(axes);
+        let mut shape = Vec::new();
+        for (axis, &n) in self.shape.iter().enumerate() {
+            if !axes.contains(&axis) {
+                shape.push(n)
+            } else if keepdims {
+                shape.push(1)
+            }
         }
+        shape
     }
 
-    pub fn sum_to(&self, shape: &[usize]) -> Array {
-        if self.shape == shape {
-            return self.clone();
+    fn normalize_axes(&self, axes: Option<&[usize]>) -> Vec<usize> {
+        let Some(axes) = axes else {
+            return (0..self.shape.len()).collect();
+        };
+        let mut axes = axes.to_vec();
+        axes.sort_unstable();
+        axes.dedup();
+        if let Some(axis) = axes.iter().find(|&&axis| axis >= self.shape.len()) {
+            panic!("axis {} is out of bounds for {:?}", axis, self.shape)
         }
+        axes
+    }
+
+    pub fn broadcast_to(&self, shape: &[usize]) -> Array<T> {
+        self.try_broadcast_to(shape)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        let Some(lead) = self.shape.len().checked_sub(shape.len()) else {
-            panic!("failed to sum {:?} to {:?}", shape, self.shape)
+    pub fn try_broadcast_to(&self, shape: &[usize]) -> Result<Array<T>> {
+        if self.shape == shape {
+            return Ok(self.clone());
+        }
+        let error = || DezeroError::Broadcast {
+            from: self.shape.clone(),
+            to: shape.to_vec(),
         };
+        let lead = shape
+            .len()
+            .checked_sub(self.shape.len())
+            .ok_or_else(error)?;
+
+        let mut strides = vec![0; lead];
+        for (axis, (&n, &stride)) in self.shape.iter().zip(self.strides.iter()).enumerate() {
+            match (n, shape[lead + axis]) {
+                (n, m) if n == m => strides.push(stride),
+                (1, _) => strides.push(0),
+                _ => return Err(error()),
+            }
+        }
 
-        let tmp = vec![1; lead];
-        let new_shape = tmp
-            .into_iter()
-            .chain(shape.iter().cloned())
-            .collect::<Vec<_>>();
+        Ok(self.view(shape.to_vec(), strides, self.offset))
+    }
 
-        let mut axes = Vec::new();
-        for (axis, (i, j)) in self.shape.iter().zip(new_shape.iter()).enumerate() {
-            match (i, j) {
-                (i, j) if i == j => {}
-                (_, 1) => {
-                    axes.push(axis);
-                }
-                _ => panic!("failed to sum {:?} to {:?}", self.shape, shape),
-            }
+    pub fn reshape(self, new_shape: &[usize]) -> Array<T> {
+        self.try_reshape(new_shape)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_reshape(self, new_shape: &[usize]) -> Result<Array<T>> {
+        let new_size = new_shape.iter().product();
+        if self.size != new_size {
+            return Err(DezeroError::ShapeMismatch {
+                op: "reshape",
+                lhs: self.shape,
+                rhs: new_shape.to_vec(),
+            });
+        }
+        if self.shape == new_shape {
+            return Ok(self);
+        }
+        let array = self.contiguous();
+        Ok(array.view(
+            new_shape.to_vec(),
+            contiguous_strides(new_shape),
+            array.offset,
+        ))
+    }
+
+    pub fn transpose(&self) -> Array<T> {
+        match self.shape.len() {
+            0 | 1 => self.clone(),
+            n => self.permute(&(0..n).rev().collect::<Vec<_>>()),
         }
+    }
 
-        let mut data = self.data.clone();
-        let dim = self.shape.len();
+    pub fn transpose_axes(&self, axes: &[usize]) -> Array<T> {
+        self.permute(axes)
+    }
 
-        let mut steps = vec![1];
-        for x in self.shape.iter().rev() {
-            steps.push(steps.last().unwrap() * x);
+    pub fn permute(&self, axes: &[usize]) -> Array<T> {
+        if !is_permutation(axes, self.shape.len()) {
+            panic!(
+                "{:?} is not a permutation of the axes of {:?}",
+                axes, self.shape
+            )
         }
 
-        for axis in axes {
-            let mut new_data = Vec::new();
-            let step = steps[dim - axis - 1];
-            for i in 0..(data.len() / steps[dim - axis]) {
-                for j in 0..step {
-                    new_data.push(
-                        data[steps[dim - axis] * i + j..]
-                            .iter()
-                            .step_by(step)
-                            .take(self.shape[axis])
-                            .sum(),
-                    )
-                }
+        let shape = axes.iter().map(|&axis| self.shape[axis]).collect();
+        let strides = axes.iter().map(|&axis| self.strides[axis]).collect();
+        self.view(shape, strides, self.offset)
+    }
+
+    pub fn swap_axes(&self, axis0: usize, axis1: usize) -> Array<T> {
+        let mut axes = (0..self.shape.len()).collect::<Vec<_>>();
+        axes.swap(axis0, axis1);
+        self.permute(&axes)
+    }
+
+    pub fn slice_axis(&self, axis: usize, start: usize, end: usize, step: usize) -> Array<T> {
+        let n = self.shape[axis];
+        if step == 0 || start > end || end > n {
+            panic!(
+                "invalid slice {}..{} with step {} for axis {} of {:?}",
+                start, end, step, axis, self.shape
+            )
+        }
+
+        let mut shape = self.shape.clone();
+        let mut strides = self.strides.clone();
+        shape[axis] = (end - start).div_ceil(step);
+        strides[axis] *= step;
+        let offset = if shape[axis] == 0 {
+            self.offset
+        } else {
+            self.offset + start * self.strides[axis]
+        };
+        self.view(shape, strides, offset)
+    }
+
+    pub fn concat(arrays: &[&Array<T>], axis: usize) -> Array<T> {
+        let Some(first) = arrays.first() else {
+            panic!("cannot concatenate an empty list of arrays")
+        };
+        let mut shape = first.shape.clone();
+        if axis >= shape.len() {
+            panic!("axis {} is out of bounds for {:?}", axis, shape)
+        }
+        for array in arrays {
+            let consistent = array.shape.len() == shape.len()
+                && (0..shape.len()).all(|i| i == axis || array.shape[i] == shape[i]);
+            if !consistent {
+                panic!(
+                    "failed to concatenate {:?} to {:?}",
+                    array.shape, first.shape
+                )
             }
-            data = new_data;
         }
+        shape[axis] = arrays.iter().map(|array| array.shape[axis]).sum();
 
-        Array::new(data, shape.to_vec())
+        let outer: usize = shape[..axis].iter().product();
+        let arrays = arrays.iter().map(|x| x.contiguous()).collect::<Vec<_>>();
+
+        let mut data = Vec::with_capacity(shape.iter().product());
+        for i in 0..outer {
+            for array in &arrays {
+                let len: usize = array.shape[axis..].iter().product();
+                data.extend_from_slice(&array.get_data()[len * i..len * (i + 1)]);
+            }
+        }
+        Array::new(data, shape)
     }
 
-    pub fn sum_with_axis(&self, axis: usize) -> Array {
-        let mut shape = self.shape.clone();
-        shape[axis] = 1;
-        self.sum_to(&shape)
+    pub fn stack(arrays: &[&Array<T>], axis: usize) -> Array<T> {
+        let expanded = arrays
+            .iter()
+            .map(|x| {
+                let mut shape = x.shape.clone();
+                shape.insert(axis, 1);
+                (*x).clone().reshape(&shape)
+            })
+            .collect::<Vec<_>>();
+        Array::concat(&expanded.iter().collect::<Vec<_>>(), axis)
+    }
+
+    pub fn split(&self, sections: &[usize], axis: usize) -> Vec<Array<T>> {
+        if sections.iter().sum::<usize>() != self.shape[axis] {
+            panic!(
+                "sections {:?} do not add up to axis {} of {:?}",
+                sections, axis, self.shape
+            )
+        }
+        let mut start = 0;
+        sections
+            .iter()
+            .map(|&len| {
+                start += len;
+                self.slice_axis(axis, start - len, start, 1)
+            })
+            .collect()
+    }
+}
+
+impl<T: Numeric> Array<T> {
+    pub fn sum(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        self.reduce(axes, keepdims, T::ZERO, |acc, x| acc + x)
     }
 
-    pub fn broadcast_to(&self, shape: &[usize]) -> Array {
+    pub fn max(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        self.reduce(axes, keepdims, T::MIN, T::maximum)
+    }
+
+    pub fn min(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        self.reduce(axes, keepdims, T::MAX, T::minimum)
+    }
+
+    pub fn prod(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        self.reduce(axes, keepdims, T::ONE, |acc, x| acc * x)
+    }
+
+    pub fn argmax(&self, axis: Option<usize>) -> Array<i64> {
+        self.arg_reduce(axis, |x, best| x > best)
+    }
+
+    pub fn argmin(&self, axis: Option<usize>) -> Array<i64> {
+        self.arg_reduce(axis, |x, best| x < best)
+    }
+
+    pub fn argmax_mask(&self, axes: Option<&[usize]>) -> Array<T> {
+        self.arg_mask(axes, |x, best| x > best)
+    }
+
+    pub fn argmin_mask(&self, axes: Option<&[usize]>) -> Array<T> {
+        self.arg_mask(axes, |x, best| x < best)
+    }
+
+    fn reduce<F>(&self, axes: Option<&[usize]>, keepdims: bool, init: T, f: F) -> Array<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        let shape = self.reduced_shape(axes, true);
+        let y = self.reduce_to(&shape, init, f);
+        if keepdims {
+            y
+        } else {
+            y.reshape(&self.reduced_shape(axes, false))
+        }
+    }
+
+    pub fn sum_to(&self, shape: &[usize]) -> Array<T> {
+        self.try_sum_to(shape).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_sum_to(&self, shape: &[usize]) -> Result<Array<T>> {
         if self.shape == shape {
-            return self.clone();
+            return Ok(self.clone());
         }
-        let Some(lead) = shape.len().checked_sub(self.shape.len()) else {
-            panic!("failed to broadcast {:?} to {:?}", self.shape, shape)
+
+        let error = || DezeroError::ShapeMismatch {
+            op: "sum_to",
+            lhs: self.shape.clone(),
+            rhs: shape.to_vec(),
         };
+        let lead = self
+            .shape
+            .len()
+            .checked_sub(shape.len())
+            .ok_or_else(error)?;
 
         let tmp = vec![1; lead];
-        let old_shape = tmp
+        let new_shape = tmp
             .into_iter()
-            .chain(self.shape.clone())
+            .chain(shape.iter().cloned())
             .collect::<Vec<_>>();
 
-        let data = broadcast_to(&self.data, &old_shape, shape);
+        for (i, j) in self.shape.iter().zip(new_shape.iter()) {
+            match (i, j) {
+                (i, j) if i == j => {}
+                (_, 1) => {}
+                _ => return Err(error()),
+            }
+        }
 
-        Array::new(data, shape.to_vec())
+        self.reduce_to(&new_shape, T::ZERO, |acc, x| acc + x)
+            .try_reshape(shape)
     }
 
-    pub fn reshape(self, new_shape: &[usize]) -> Array {
-        let new_size = new_shape.iter().product();
-        if self.size != new_size {
-            panic!("Cannot convert {:?} to {:?}", self.shape, new_shape)
-        }
-        Array {
-            data: self.data,
-            shape: new_shape.to_vec(),
-            size: self.size,
+    fn reduce_to<F>(&self, shape: &[usize], init: T, f: F) -> Array<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        let strides = contiguous_strides(shape)
+            .into_iter()
+            .zip(shape.iter())
+            .map(|(stride, &n)| if n == 1 { 0 } else { stride })
+            .collect::<Vec<_>>();
+
+        let mut data = vec![init; shape.iter().product()];
+        for (x, i) in self.iter().zip(Offsets::new(&self.shape, &strides, 0)) {
+            data[i] = f(data[i], x);
         }
+        Array::new(data, shape.to_vec())
     }
 
-    pub fn transpose(&self) -> Array {
-        match self.shape.len() {
-            0 | 1 => self.clone(),
-            2 => self.transpose2d(),
-            _ => todo!("transpose for array with dim > 3 is not implemented"),
+    // Returns the data with the reduced axes moved to the end so that every group being reduced
+    // is a contiguous chunk, along with the size of one chunk and the permutation used.
+    fn group_reduced(&self, axes: &[usize]) -> (Array<T>, usize, Vec<usize>) {
+        let mut perm = (0..self.shape.len())
+            .filter(|axis| !axes.contains(axis))
+            .collect::<Vec<_>>();
+        perm.extend_from_slice(axes);
+        let inner: usize = axes.iter().map(|&axis| self.shape[axis]).product();
+        if inner == 0 {
+            panic!("cannot take the arg of an empty axis of {:?}", self.shape)
         }
+        (self.permute(&perm).contiguous(), inner, perm)
     }
 
-    fn transpose2d(&self) -> Array {
-        let (m, n) = (self.shape[0], self.shape[1]);
-        let mut data = Vec::with_capacity(self.size);
-        for i in 0..n {
-            for j in 0..m {
-                data.push(self.data[n * j + i])
-            }
+    fn arg_indices<F>(data: &[T], inner: usize, better: F) -> Vec<usize>
+    where
+        F: Fn(T, T) -> bool,
+    {
+        data.chunks(inner)
+            .map(|group| {
+                (1..inner).fold(0, |best, i| {
+                    if better(group[i], group[best]) {
+                        i
+                    } else {
+                        best
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn arg_reduce<F>(&self, axis: Option<usize>, better: F) -> Array<i64>
+    where
+        F: Fn(T, T) -> bool,
+    {
+        let axes = axis.map(|axis| vec![axis]);
+        let axes = self.normalize_axes(axes.as_deref());
+        let (x, inner, _) = self.group_reduced(&axes);
+        let data = Array::arg_indices(&x.get_data(), inner, better)
+            .into_iter()
+            .map(|i| i as i64)
+            .collect();
+        Array::new(data, self.reduced_shape(Some(&axes), false))
+    }
+
+    fn arg_mask<F>(&self, axes: Option<&[usize]>, better: F) -> Array<T>
+    where
+        F: Fn(T, T) -> bool,
+    {
+        let axes = self.normalize_axes(axes);
+        let (x, inner, perm) = self.group_reduced(&axes);
+        let mut data = vec![T::ZERO; self.size];
+        for (group, i) in Array::arg_indices(&x.get_data(), inner, better)
+            .into_iter()
+            .enumerate()
+        {
+            data[group * inner + i] = T::ONE;
         }
-        Array {
-            data,
-            shape: vec![n, m],
-            size: m * n,
+
+        let mut inv = vec![0; perm.len()];
+        for (i, &axis) in perm.iter().enumerate() {
+            inv[axis] = i;
         }
+        Array::new(data, x.shape).permute(&inv)
     }
 
-    pub fn matmul(&self, rhs: &Array) -> Array {
+    pub fn matmul(&self, rhs: &Array<T>) -> Array<T> {
+        self.try_matmul(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_matmul(&self, rhs: &Array<T>) -> Result<Array<T>> {
         let ldim = self.shape.len();
         let rdim = rhs.shape.len();
+        let error = || DezeroError::ShapeMismatch {
+            op: "matmul",
+            lhs: self.shape.clone(),
+            rhs: rhs.shape.clone(),
+        };
         if ldim == 0 || rdim == 0 {
-            panic!("Scaler cannot be multiplied with matrices using matmul.")
+            return Err(error());
         }
 
         let (l_squeeze_flag, r_squeeze_flag): (bool, bool);
@@ -189,192 +466,177 @@ impl Array {
         };
 
         if m != m_ {
-            panic!("invalid shape")
+            return Err(error());
         }
 
-        let mut new_shape = shape_after_broadcast(lstackshape, rstackshape).unwrap();
-        new_shape.append(&mut vec![l, n]);
-
-        let new_size: usize = new_shape.iter().product();
-
-        let data = self
-            .data
-            .chunks(l * m)
-            .cycle()
-            .take(new_size / l / n)
-            .zip(rhs.data.chunks(m * n).cycle().take(new_size / l / n))
-            .flat_map(|(lhs, rhs)| matmul_2d(lhs, rhs, (l, m, n)))
-            .collect();
+        let stackshape = shape_after_broadcast(lstackshape, rstackshape).ok_or_else(error)?;
+        let with_matrix = |rows, cols| {
+            let mut shape = stackshape.clone();
+            shape.extend([rows, cols]);
+            shape
+        };
 
+        let lhs = self.clone().reshape(&[lstackshape, &[l, m]].concat());
+        let rhs_t = rhs
+            .clone()
+            .reshape(&[rstackshape, &[m, n]].concat())
+            .swap_axes(rstackshape.len(), rstackshape.len() + 1)
+            .contiguous();
+
+        let data = matmul_batched(
+            &lhs.get_data(),
+            &broadcast_offsets(lstackshape, &stackshape, l * m),
+            &rhs_t.get_data(),
+            &broadcast_offsets(rstackshape, &stackshape, m * n),
+            (l, m, n),
+        );
+
+        let mut new_shape = with_matrix(l, n);
         let len = new_shape.len();
-        if l_squeeze_flag {
+        if r_squeeze_flag {
             new_shape.remove(len - 1);
         }
-        if r_squeeze_flag {
+        if l_squeeze_flag {
             new_shape.remove(len - 2);
         }
 
-        Array::new(data, new_shape)
+        Array::try_new(data, new_shape)
     }
 
-    pub fn relu_max(&self, rhs: f32) -> Array {
-        let data = self.data.iter().map(|a| a.max(rhs)).collect();
-        Array::new(data, self.shape.clone())
+    pub fn relu_max(&self, rhs: T) -> Array<T> {
+        self.map(|a| a.maximum(rhs))
     }
 
-    pub fn relu_mask(&self, rhs: &Array, threshold: f32) -> Array {
-        let data = self
-            .data
-            .iter()
-            .zip(rhs.data.iter())
-            .map(|(&x, &y)| if x > threshold { y } else { 0. })
-            .collect();
-        Array::new(data, self.shape.clone())
+    pub fn relu_mask(&self, rhs: &Array<T>, threshold: T) -> Array<T> {
+        self.broadcast_zip(rhs, |x, y| if x > threshold { y } else { T::ZERO })
     }
 
-    pub fn clip(&self, lowerbound: f32, upperbound: f32) -> Array {
-        let data = self
-            .data
-            .iter()
-            .map(|&x| x.min(upperbound).max(lowerbound))
-            .collect();
-        Array::new(data, self.shape.clone())
+    pub fn clip(&self, lowerbound: T, upperbound: T) -> Array<T> {
+        self.map(|x| x.minimum(upperbound).maximum(lowerbound))
     }
 
-    pub fn max(&self, axis: usize) -> Array {
-        let stride: usize = self.shape[..axis].iter().product();
-        let step: usize = if axis >= self.shape.len() {
-            1
-        } else {
-            self.shape[axis + 1..].iter().product()
-        };
-        let num_summand: usize = self.shape[axis];
+    pub fn equal(&self, rhs: &Array<T>) -> Array<bool> {
+        self.broadcast_zip(rhs, |x, y| x == y)
+    }
 
-        let mut data: Vec<f32> = Vec::new();
-        for i in 0..stride {
-            for j in 0..step {
-                data.push(
-                    self.data[step * num_summand * i + j..]
-                        .iter()
-                        .step_by(step)
-                        .take(num_summand)
-                        .fold(f32::NEG_INFINITY, |x, y| x.max(*y)),
-                )
-            }
-        }
+    pub fn gt(&self, rhs: &Array<T>) -> Array<bool> {
+        self.broadcast_zip(rhs, |x, y| x > y)
+    }
 
-        let mut shape = self.shape.clone();
-        shape[axis] = 1;
-        Array::new(data, shape)
+    pub fn ge(&self, rhs: &Array<T>) -> Array<bool> {
+        self.broadcast_zip(rhs, |x, y| x >= y)
+    }
+
+    pub fn lt(&self, rhs: &Array<T>) -> Array<bool> {
+        self.broadcast_zip(rhs, |x, y| x < y)
+    }
+
+    pub fn le(&self, rhs: &Array<T>) -> Array<bool> {
+        self.broadcast_zip(rhs, |x, y| x <= y)
+    }
+}
+
+impl<T: Float> Array<T> {
+    define_map_functions!(exp, ln, sin, cos, tan, sinh, cosh, tanh, sqrt, abs);
+
+    pub fn powi(&self, n: i32) -> Array<T> {
+        self.map(|a| a.powi(n))
+    }
+
+    pub fn powf(&self, n: T) -> Array<T> {
+        self.map(|a| a.powf(n))
+    }
+
+    pub fn mean(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        let axes = self.normalize_axes(axes);
+        let count: usize = axes.iter().map(|&axis| self.shape[axis]).product();
+        self.sum(Some(&axes), keepdims) / T::from_f64(count as f64)
+    }
+
+    pub fn var(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        let diff = self - self.mean(axes, true);
+        diff.powi(2).mean(axes, keepdims)
+    }
+
+    pub fn std(&self, axes: Option<&[usize]>, keepdims: bool) -> Array<T> {
+        self.var(axes, keepdims).map(T::sqrt)
     }
 }
 
 macro_rules! impl_op {
     ($trait: ident, $fname: ident) => {
-        impl $trait for &Array {
-            type Output = Array;
+        impl<T: Numeric> $trait for &Array<T> {
+            type Output = Array<T>;
             fn $fname(self, rhs: Self) -> Self::Output {
-                if self.shape.is_empty() {
-                    return self.data[0].$fname(rhs);
-                }
-                if rhs.shape.is_empty() {
-                    return self.$fname(rhs.data[0]);
-                }
-                if self.shape != rhs.shape {
-                    let new_shape =
-                        shape_after_broadcast(&self.shape, &rhs.shape).expect(&format!(
-                            "Two arrays must have the same shape\nlhs: {:?}\nrhs: {:?}",
-                            self, rhs,
-                        ));
-
-                    let ldata = self.broadcast_to(&new_shape).data;
-                    let rdata = rhs.broadcast_to(&new_shape).data;
-
-                    let data = ldata
-                        .iter()
-                        .zip(rdata)
-                        .map(|(x, y)| f32::$fname(*x, y))
-                        .collect();
-                    let size = new_shape.iter().product();
-                    Array {
-                        data,
-                        shape: new_shape,
-                        size,
-                    }
-                } else {
+                if self.shape == rhs.shape {
                     let data = self
-                        .data
                         .iter()
-                        .zip(rhs.data.iter())
-                        .map(|(x, y)| f32::$fname(*x, y))
+                        .zip(rhs.iter())
+                        .map(|(x, y)| x.$fname(y))
                         .collect();
-                    Array {
-                        data,
-                        shape: self.shape.clone(),
-                        size: self.size,
-                    }
+                    Array::new(data, self.shape.clone())
+                } else {
+                    self.broadcast_zip(rhs, T::$fname)
                 }
             }
         }
 
-        impl $trait<Array> for &Array {
-            type Output = Array;
-            fn $fname(self, rhs: Array) -> Self::Output {
+        impl<T: Numeric> $trait<Array<T>> for &Array<T> {
+            type Output = Array<T>;
+            fn $fname(self, rhs: Array<T>) -> Self::Output {
                 self.$fname(&rhs)
             }
         }
 
-        impl $trait<&Array> for Array {
-            type Output = Array;
-            fn $fname(self, rhs: &Array) -> Self::Output {
+        impl<T: Numeric> $trait<&Array<T>> for Array<T> {
+            type Output = Array<T>;
+            fn $fname(self, rhs: &Array<T>) -> Self::Output {
                 (&self).$fname(rhs)
             }
         }
 
-        impl $trait for Array {
-            type Output = Array;
+        impl<T: Numeric> $trait for Array<T> {
+            type Output = Array<T>;
             fn $fname(self, rhs: Self) -> Self::Output {
                 self.$fname(&rhs)
             }
         }
 
-        impl $trait<f32> for Array {
-            type Output = Array;
-            fn $fname(self, rhs: f32) -> Self::Output {
+        impl<T: Numeric> $trait<T> for Array<T> {
+            type Output = Array<T>;
+            fn $fname(self, rhs: T) -> Self::Output {
                 (&self).$fname(rhs)
             }
         }
 
-        impl $trait<Array> for f32 {
-            type Output = Array;
-            fn $fname(self, rhs: Array) -> Self::Output {
-                self.$fname(&rhs)
+        impl<T: Numeric> $trait<T> for &Array<T> {
+            type Output = Array<T>;
+            fn $fname(self, rhs: T) -> Self::Output {
+                self.map(|x| x.$fname(rhs))
             }
         }
 
-        impl $trait<f32> for &Array {
-            type Output = Array;
-            fn $fname(self, rhs: f32) -> Self::Output {
-                let data = self.data.iter().map(|x| f32::$fname(*x, rhs)).collect();
-                Array {
-                    data,
-                    shape: self.shape.clone(),
-                    size: self.size,
-                }
+        impl_scalar_op!($trait, $fname, f32, f64, i32, i64);
+    };
+}
+
+macro_rules! impl_scalar_op {
+    ($trait: ident, $fname: ident, $($type: ty),*) => {
+        $(
+        impl $trait<Array<$type>> for $type {
+            type Output = Array<$type>;
+            fn $fname(self, rhs: Array<$type>) -> Self::Output {
+                self.$fname(&rhs)
             }
         }
 
-        impl $trait<&Array> for f32 {
-            type Output = Array;
-            fn $fname(self, rhs: &Array) -> Self::Output {
-                let data = rhs.data.iter().map(|x| f32::$fname(self, x)).collect();
-                Array {
-                    data,
-                    shape: rhs.shape.clone(),
-                    size: rhs.size,
-                }
+        impl $trait<&Array<$type>> for $type {
+            type Output = Array<$type>;
+            fn $fname(self, rhs: &Array<$type>) -> Self::Output {
+                rhs.map(|x| self.$fname(x))
             }
         }
+        )*
     };
 }
 
@@ -383,16 +645,15 @@ impl_op!(Sub, sub);
 impl_op!(Mul, mul);
 impl_op!(Div, div);
 
-impl Neg for Array {
-    type Output = Array;
+impl<T: Numeric + Neg<Output = T>> Neg for Array<T> {
+    type Output = Array<T>;
     fn neg(self) -> Self::Output {
-        let data = self.data.iter().map(|a| -a).collect();
-        Array::new(data, self.shape.clone())
+        self.map(|a| -a)
     }
 }
 
-impl Neg for &Array {
-    type Output = Array;
+impl<T: Numeric + Neg<Output = T>> Neg for &Array<T> {
+    type Output = Array<T>;
     fn neg(self) -> Self::Output {
         -self.clone()
     }
@@ -1,14 +1,15 @@
-use super::Array;
+use super::{Array, Element, Float, Numeric};
 
-impl Array {
-    pub fn all_close(&self, rhs: &Array, tol: f32) -> bool {
+impl<T: Float> Array<T> {
+    pub fn all_close(&self, rhs: &Array<T>, tol: T) -> bool {
         (self - rhs)
-            .data
             .iter()
-            .try_for_each(|x| if x < &tol { Some(()) } else { None })
+            .try_for_each(|x| if x.abs() <= tol { Some(()) } else { None })
             .is_some()
     }
+}
 
+impl<T: Element> Array<T> {
     pub fn size(&self) -> usize {
         self.size
     }
@@ -16,15 +17,14 @@ impl Array {
 
 macro_rules! inner {
     ($x: expr, $y: expr) => {
-        $x.zip($y).fold(0., |acc, (x, y)| acc + x * y)
+        $x.zip($y).fold(T::ZERO, |acc, (&x, &y)| acc + x * y)
     };
 }
 
 pub(super) fn shape_after_broadcast(shape0: &[usize], shape1: &[usize]) -> Option<Vec<usize>> {
     let mut res = Vec::new();
     if shape0.len() <= shape1.len() {
-        for (&n, &m) in std::iter::repeat(&1)
-            .take(shape1.len() - shape0.len())
+        for (&n, &m) in std::iter::repeat_n(&1, shape1.len() - shape0.len())
             .chain(shape0.iter())
             .zip(shape1.iter())
         {
@@ -36,8 +36,7 @@ pub(super) fn shape_after_broadcast(shape0: &[usize], shape1: &[usize]) -> Optio
             }
         }
     } else {
-        for (&n, &m) in std::iter::repeat(&1)
-            .take(shape0.len() - shape1.len())
+        for (&n, &m) in std::iter::repeat_n(&1, shape0.len() - shape1.len())
             .chain(shape1.iter())
             .zip(shape0.iter())
         {
@@ -52,47 +51,183 @@ pub(super) fn shape_after_broadcast(shape0: &[usize], shape1: &[usize]) -> Optio
     Some(res)
 }
 
-pub(super) fn broadcast_to(data: &[f32], old_shape: &[usize], new_shape: &[usize]) -> Vec<f32> {
-    let mut axes = Vec::new();
-    let mut dups = Vec::new();
-    for (axis, (i, j)) in old_shape.iter().zip(new_shape.iter()).enumerate() {
-        match (i, j) {
-            (i, j) if i == j => {}
-            (1, &dup) => {
-                axes.push(axis);
-                dups.push(dup);
+const MATMUL_BLOCK: usize = 64;
+const MATMUL_PARALLEL_THRESHOLD: usize = 1 << 18;
+
+// Multiplies one pair of matrices per batch entry. Entry `b` reads the row-major (l, m) matrix at
+// `lhs[lhs_offsets[b]..]` and the right hand side, already transposed to (n, m), at
+// `rhs_t[rhs_offsets[b]..]`, so that every inner product reads two contiguous rows. A broadcast
+// operand repeats its offset instead of being copied once per batch entry.
+pub(super) fn matmul_batched<T: Numeric>(
+    lhs: &[T],
+    lhs_offsets: &[usize],
+    rhs_t: &[T],
+    rhs_offsets: &[usize],
+    (l, m, n): (usize, usize, usize),
+) -> Vec<T> {
+    let batch = lhs_offsets.len();
+    let mut data = vec![T::ZERO; batch * l * n];
+    if data.is_empty() {
+        return data;
+    }
+
+    let threads = if batch * l * m * n < MATMUL_PARALLEL_THRESHOLD {
+        1
+    } else {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    };
+    let rows_per_thread = (batch * l).div_ceil(threads);
+    let operands = (lhs, lhs_offsets, rhs_t, rhs_offsets);
+
+    if threads == 1 {
+        matmul_rows(operands, &mut data, 0, (l, m, n));
+    } else {
+        std::thread::scope(|scope| {
+            for (i, out) in data.chunks_mut(rows_per_thread * n).enumerate() {
+                scope.spawn(move || matmul_rows(operands, out, i * rows_per_thread, (l, m, n)));
+            }
+        });
+    }
+    data
+}
+
+type MatmulOperands<'a, T> = (&'a [T], &'a [usize], &'a [T], &'a [usize]);
+
+// Fills `out` with the output rows starting at the global row `first_row`, where the rows of
+// all matrices in the batch are numbered consecutively. The loops are blocked over both the
+// output columns and the inner dimension, so a block of `rhs_t` stays in cache while every row
+// of the chunk is multiplied with it.
+fn matmul_rows<T: Numeric>(
+    (lhs, lhs_offsets, rhs_t, rhs_offsets): MatmulOperands<T>,
+    out: &mut [T],
+    first_row: usize,
+    (l, m, n): (usize, usize, usize),
+) {
+    let num_rows = out.len() / n;
+    for k0 in (0..m).step_by(MATMUL_BLOCK) {
+        let k1 = (k0 + MATMUL_BLOCK).min(m);
+        for j0 in (0..n).step_by(MATMUL_BLOCK) {
+            let j1 = (j0 + MATMUL_BLOCK).min(n);
+            for r in 0..num_rows {
+                let row = first_row + r;
+                let (b, i) = (row / l, row % l);
+                let lhs_row = lhs_offsets[b] + i * m;
+                let lhs_row = &lhs[lhs_row + k0..lhs_row + k1];
+                for j in j0..j1 {
+                    let rhs_row = rhs_offsets[b] + j * m;
+                    let rhs_row = &rhs_t[rhs_row + k0..rhs_row + k1];
+                    out[r * n + j] = out[r * n + j] + dot(lhs_row, rhs_row);
+                }
             }
-            _ => panic!("failed to broadcast {:?} to {:?}", data, new_shape),
         }
     }
+}
 
-    let mut data = data.to_vec();
-    let dim = new_shape.len();
+// Offsets of the (rows, cols) matrices of a contiguous array with leading shape `stack` when the
+// array is broadcast to the leading shape `target`.
+pub(super) fn broadcast_offsets(stack: &[usize], target: &[usize], matrix: usize) -> Vec<usize> {
+    let lead = target.len() - stack.len();
+    let mut strides = vec![0; lead];
+    for (axis, (&n, stride)) in stack.iter().zip(contiguous_strides(stack)).enumerate() {
+        let broadcast = n != target[lead + axis];
+        strides.push(if broadcast { 0 } else { stride * matrix });
+    }
+    Offsets::new(target, &strides, 0).collect()
+}
 
-    let mut chunk_sizes = vec![1];
-    for x in new_shape.iter().rev() {
-        chunk_sizes.push(chunk_sizes.last().unwrap() * x);
+fn dot<T: Numeric>(x: &[T], y: &[T]) -> T {
+    let mut acc = [T::ZERO; 8];
+    let (x_chunks, y_chunks) = (x.chunks_exact(8), y.chunks_exact(8));
+    let rest = inner!(x_chunks.remainder().iter(), y_chunks.remainder().iter());
+    for (x, y) in x_chunks.zip(y_chunks) {
+        for k in 0..8 {
+            acc[k] = acc[k] + x[k] * y[k];
+        }
     }
+    acc.iter().fold(rest, |sum, &x| sum + x)
+}
 
-    for (&axis, &dup) in axes.iter().zip(dups.iter()) {
-        data = data
-            .chunks(chunk_sizes[dim - axis - 1])
-            .map(|c| c.repeat(dup))
-            .flatten()
-            .collect();
+pub(super) fn is_permutation(axes: &[usize], dim: usize) -> bool {
+    let mut seen = vec![false; dim];
+    axes.len() == dim
+        && axes
+            .iter()
+            .all(|&axis| axis < dim && !std::mem::replace(&mut seen[axis], true))
+}
+
+pub(super) fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
     }
-    data
+    strides
 }
 
-pub(super) fn matmul_2d(lhs: &[f32], rhs: &[f32], (l, m, n): (usize, usize, usize)) -> Vec<f32> {
-    let mut data = Vec::with_capacity(l * n);
-    for i in 0..l {
-        for j in 0..n {
-            data.push(inner!(
-                lhs[m * i..].iter().take(m),
-                rhs[j..].iter().step_by(n)
-            ));
+pub(super) struct Offsets<'a> {
+    shape: &'a [usize],
+    strides: &'a [usize],
+    index: Vec<usize>,
+    offset: usize,
+    remaining: usize,
+}
+
+impl<'a> Offsets<'a> {
+    pub(super) fn new(shape: &'a [usize], strides: &'a [usize], offset: usize) -> Self {
+        Offsets {
+            shape,
+            strides,
+            index: vec![0; shape.len()],
+            offset,
+            remaining: shape.iter().product(),
+        }
+    }
+}
+
+impl Iterator for Offsets<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let current = self.offset;
+        for axis in (0..self.shape.len()).rev() {
+            self.index[axis] += 1;
+            self.offset += self.strides[axis];
+            if self.index[axis] < self.shape[axis] {
+                break;
+            }
+            self.offset -= self.strides[axis] * self.shape[axis];
+            self.index[axis] = 0;
+        }
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+pub(super) enum Elements<'a, T> {
+    Contiguous(std::slice::Iter<'a, T>),
+    Strided(&'a [T], Offsets<'a>),
+}
+
+impl<T: Copy> Iterator for Elements<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self {
+            Elements::Contiguous(iter) => iter.next().copied(),
+            Elements::Strided(data, offsets) => offsets.next().map(|i| data[i]),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Elements::Contiguous(iter) => iter.size_hint(),
+            Elements::Strided(_, offsets) => offsets.size_hint(),
         }
     }
-    data
 }
@@ -0,0 +1,240 @@
+use crate::{
+    array::Array,
+    config,
+    functions::{self as F, FuncBox},
+    variable::VBox,
+};
+use std::{
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
+};
+
+#[allow(clippy::mutable_key_type)]
+pub fn grad(
+    outputs: &[VBox],
+    inputs: &[VBox],
+    grad_outputs: Option<&[VBox]>,
+    create_graph: bool,
+) -> Vec<VBox> {
+    let mut grads: HashMap<VBox, VBox> = HashMap::new();
+    let mut funcs = BinaryHeap::new();
+    let mut seen_set = HashSet::new();
+
+    for (i, y) in outputs.iter().enumerate() {
+        let gy = match grad_outputs {
+            Some(gys) => gys[i].clone(),
+            None => VBox::new(Array::ones(&y.get_shape())),
+        };
+        accumulate(&mut grads, y, gy);
+        push_creator(&mut funcs, &mut seen_set, y);
+    }
+
+    let _guard = config::using_config("enable_backprop", create_graph);
+    while let Some(f) = funcs.pop() {
+        let gy = match grads.get(&f.get_output().upgrade()) {
+            Some(gy) => gy.clone(),
+            None => continue,
+        };
+        let gxs = f.backward(gy);
+
+        for (x, gx) in f.get_inputs().iter().zip(gxs) {
+            accumulate(&mut grads, x, gx);
+            push_creator(&mut funcs, &mut seen_set, x);
+        }
+    }
+
+    inputs
+        .iter()
+        .map(|x| match grads.get(x) {
+            Some(gx) => gx.clone(),
+            None => VBox::new(Array::zeros(&x.get_shape())),
+        })
+        .collect()
+}
+
+pub fn jacobian(output: &VBox, input: &VBox, create_graph: bool) -> VBox {
+    let y_shape = output.get_shape();
+    let x_shape = input.get_shape();
+    let y_size = y_shape.iter().product::<usize>();
+    let x_size = x_shape.iter().product::<usize>();
+    let shape: Vec<usize> = y_shape.iter().chain(&x_shape).copied().collect();
+    if y_size == 0 {
+        return VBox::new(Array::zeros(&shape));
+    }
+    let _guard = config::using_config("enable_backprop", create_graph);
+
+    let rows = (0..y_size)
+        .map(|i| {
+            let mut onehot = vec![0.; y_size];
+            onehot[i] = 1.;
+            let gy = VBox::new(Array::new(onehot, y_shape.clone()));
+            let gx = grad(
+                std::slice::from_ref(output),
+                std::slice::from_ref(input),
+                Some(&[gy]),
+                create_graph,
+            );
+            gx[0].reshape(vec![x_size])
+        })
+        .collect::<Vec<_>>();
+
+    F::stack(&rows, 0).reshape(shape)
+}
+
+pub fn hessian(output: &VBox, input: &VBox) -> VBox {
+    assert!(
+        output.get_shape().iter().product::<usize>() == 1,
+        "hessian: output must be a scalar"
+    );
+    let gx = grad(
+        std::slice::from_ref(output),
+        std::slice::from_ref(input),
+        None,
+        true,
+    );
+    jacobian(&gx[0], input, false)
+}
+
+pub fn jvp(f: impl Fn(&[VBox]) -> VBox, primals: &[Array], tangents: &[Array]) -> (Array, Array) {
+    assert_eq!(
+        primals.len(),
+        tangents.len(),
+        "jvp: the number of primals and tangents must match"
+    );
+    let _no_grad = config::no_grad();
+    let x = primals
+        .iter()
+        .zip(tangents)
+        .map(|(p, t)| {
+            assert_eq!(
+                p.get_shape(),
+                t.get_shape(),
+                "jvp: the tangent must have the same shape as its primal"
+            );
+            let x = VBox::new(p.clone());
+            x.set_tangent(t.clone());
+            x
+        })
+        .collect::<Vec<_>>();
+    let y = f(&x);
+    let ty = y
+        .get_tangent()
+        .unwrap_or_else(|| Array::zeros(&y.get_shape()));
+    (y.get_array(), ty)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradMismatch {
+    pub input: usize,
+    pub index: usize,
+    pub analytic: f64,
+    pub numeric: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradcheckReport {
+    pub mismatches: Vec<GradMismatch>,
+}
+
+impl Display for GradcheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "gradcheck failed for {} element(s)",
+            self.mismatches.len()
+        )?;
+        for m in &self.mismatches {
+            write!(
+                f,
+                "\n  input {} [{}]: analytic {}, numeric {}",
+                m.input, m.index, m.analytic, m.numeric
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GradcheckReport {}
+
+// Perturbations, steps and the comparison are computed in f64, but `f` itself
+// runs in f32 because variables only hold f32 arrays; there is no f64 path
+// until they do. Each step is measured from the perturbed input after
+// rounding, so that rounding does not bias the estimate, but f32 noise in `f`
+// still limits how small `eps`, `atol` and `rtol` can usefully be (about 1e-3,
+// 1e-4 and 1e-4).
+pub fn gradcheck(
+    f: impl Fn(&[VBox]) -> VBox,
+    inputs: &[Array],
+    eps: f64,
+    atol: f64,
+    rtol: f64,
+) -> Result<(), GradcheckReport> {
+    let x = inputs.iter().cloned().map(VBox::new).collect::<Vec<_>>();
+    let y = f(&x);
+    let gx = grad(std::slice::from_ref(&y), &x, None, false);
+
+    let _no_grad = config::no_grad();
+    let inputs = inputs.iter().map(Array::astype::<f64>).collect::<Vec<_>>();
+    let eval = |x: &[Array<f64>]| -> f64 {
+        let x = x
+            .iter()
+            .map(|x| VBox::new(x.astype::<f32>()))
+            .collect::<Vec<_>>();
+        f(&x).get_array().iter().map(f64::from).sum()
+    };
+
+    let mut mismatches = Vec::new();
+    for (i, g) in gx.iter().enumerate() {
+        let shape = inputs[i].get_shape().clone();
+        let data = inputs[i].to_vec();
+        let analytic = g.get_array().astype::<f64>().to_vec();
+
+        for j in 0..data.len() {
+            let mut shifted = inputs.to_vec();
+            let mut perturb = |delta: f64| {
+                let mut x = data.clone();
+                x[j] = (data[j] + delta) as f32 as f64;
+                let xj = x[j];
+                shifted[i] = Array::new(x, shape.clone());
+                (xj, eval(&shifted))
+            };
+            let (x_plus, y_plus) = perturb(eps);
+            let (x_minus, y_minus) = perturb(-eps);
+
+            let numeric = (y_plus - y_minus) / (x_plus - x_minus);
+            let analytic = analytic[j];
+            if (analytic - numeric).abs() > atol + rtol * numeric.abs() {
+                mismatches.push(GradMismatch {
+                    input: i,
+                    index: j,
+                    analytic,
+                    numeric,
+                });
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(GradcheckReport { mismatches })
+    }
+}
+
+#[allow(clippy::mutable_key_type)]
+fn accumulate(grads: &mut HashMap<VBox, VBox>, x: &VBox, gx: VBox) {
+    let gx = match grads.remove(x) {
+        Some(gx_old) => gx_old + gx,
+        None => gx,
+    };
+    grads.insert(x.clone(), gx);
+}
+
+fn push_creator(funcs: &mut BinaryHeap<FuncBox>, seen_set: &mut HashSet<FuncBox>, x: &VBox) {
+    if let Some(creator) = x.get_creator() {
+        if !seen_set.contains(&creator) {
+            funcs.push(creator.clone());
+            seen_set.insert(creator);
+        }
+    }
+}
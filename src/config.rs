@@ -0,0 +1,68 @@
+use std::cell::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    pub enable_backprop: bool,
+    pub train: bool,
+}
+
+thread_local! {
+    static CONFIG: Cell<Config> = const {
+        Cell::new(Config {
+            enable_backprop: true,
+            train: true,
+        })
+    };
+}
+
+pub fn get_config() -> Config {
+    CONFIG.with(|c| c.get())
+}
+
+pub fn enable_backprop() -> bool {
+    get_config().enable_backprop
+}
+
+pub fn is_train() -> bool {
+    get_config().train
+}
+
+// Restores only the setting it changed, so guards can be dropped in any order.
+#[must_use = "the previous setting is restored as soon as the guard is dropped"]
+pub struct ConfigGuard {
+    field: fn(&mut Config) -> &mut bool,
+    prev: bool,
+}
+
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        set_field(self.field, self.prev);
+    }
+}
+
+fn set_field(field: fn(&mut Config) -> &mut bool, value: bool) -> bool {
+    CONFIG.with(|c| {
+        let mut config = c.get();
+        let prev = std::mem::replace(field(&mut config), value);
+        c.set(config);
+        prev
+    })
+}
+
+pub fn using_config(name: &str, value: bool) -> ConfigGuard {
+    let field: fn(&mut Config) -> &mut bool = match name {
+        "enable_backprop" => |config| &mut config.enable_backprop,
+        "train" => |config| &mut config.train,
+        _ => panic!("unknown config {:?}", name),
+    };
+    let prev = set_field(field, value);
+    ConfigGuard { field, prev }
+}
+
+pub fn no_grad() -> ConfigGuard {
+    using_config("enable_backprop", false)
+}
+
+pub fn test_mode() -> ConfigGuard {
+    using_config("train", false)
+}
@@ -0,0 +1,82 @@
+use crate::{error::Result, functions::FuncBox, variable::VBox};
+use std::{collections::HashSet, fmt::Write, fs, io, path::Path, process::Command};
+
+pub fn get_dot_graph(output: &VBox) -> String {
+    let mut txt = String::from("digraph g {\n");
+    let mut funcs = Vec::new();
+    let mut seen_set = HashSet::new();
+
+    txt += &dot_var(output);
+    if let Some(creator) = output.get_creator() {
+        seen_set.insert(creator.clone());
+        funcs.push(creator);
+    }
+
+    while let Some(f) = funcs.pop() {
+        txt += &dot_func(&f);
+        for x in f.get_inputs() {
+            txt += &dot_var(&x);
+            if let Some(creator) = x.get_creator() {
+                if !seen_set.contains(&creator) {
+                    seen_set.insert(creator.clone());
+                    funcs.push(creator);
+                }
+            }
+        }
+    }
+
+    txt += "}\n";
+    txt
+}
+
+pub fn plot_dot_graph(output: &VBox, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let txt = get_dot_graph(output);
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("dot");
+    if format == "dot" {
+        fs::write(path, txt)?;
+        return Ok(());
+    }
+
+    let dot_path = path.with_extension("dot");
+    fs::write(&dot_path, txt)?;
+    let status = Command::new("dot")
+        .arg(format!("-T{}", format))
+        .arg(&dot_path)
+        .arg("-o")
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("dot exited with {}", status)).into());
+    }
+    Ok(())
+}
+
+fn dot_var(v: &VBox) -> String {
+    let label = match v.get_name() {
+        Some(name) => format!("{}\\n{:?}", name, v.get_shape()),
+        None => format!("{:?}", v.get_shape()),
+    };
+    format!(
+        "{} [label=\"{}\", color=orange, style=filled]\n",
+        v.id(),
+        label
+    )
+}
+
+fn dot_func(f: &FuncBox) -> String {
+    let mut txt = format!(
+        "{} [label=\"{}\\ngen {}\", color=lightblue, style=filled, shape=box]\n",
+        f.id(),
+        f.name(),
+        f.get_gen()
+    );
+    for x in f.get_inputs() {
+        writeln!(txt, "{} -> {}", x.id(), f.id()).unwrap();
+    }
+    writeln!(txt, "{} -> {}", f.id(), f.get_output().upgrade().id()).unwrap();
+    txt
+}
@@ -0,0 +1,94 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum DezeroError {
+    ShapeMismatch {
+        op: &'static str,
+        lhs: Vec<usize>,
+        rhs: Vec<usize>,
+    },
+    Broadcast {
+        from: Vec<usize>,
+        to: Vec<usize>,
+    },
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: Option<usize>,
+        message: String,
+    },
+    DanglingNode,
+    Format(String),
+    MissingKey(String),
+    UnexpectedKey(String),
+    ParamShape {
+        key: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, DezeroError>;
+
+impl Display for DezeroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DezeroError::ShapeMismatch { op, lhs, rhs } => {
+                write!(f, "{}: incompatible shapes {:?} and {:?}", op, lhs, rhs)
+            }
+            DezeroError::Broadcast { from, to } => {
+                write!(f, "failed to broadcast {:?} to {:?}", from, to)
+            }
+            DezeroError::Io(err) => write!(f, "io error: {}", err),
+            DezeroError::Parse {
+                line,
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "parse error at line {}, column {}: {}",
+                line, column, message
+            ),
+            DezeroError::Parse {
+                line,
+                column: None,
+                message,
+            } => write!(f, "parse error at line {}: {}", line, message),
+            DezeroError::DanglingNode => {
+                write!(
+                    f,
+                    "the variable referenced by the graph has already been dropped"
+                )
+            }
+            DezeroError::Format(message) => write!(f, "invalid file format: {}", message),
+            DezeroError::MissingKey(key) => write!(f, "missing key in state dict: {}", key),
+            DezeroError::UnexpectedKey(key) => {
+                write!(f, "unexpected key in state dict: {}", key)
+            }
+            DezeroError::ParamShape {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "shape mismatch for {}: expected {:?}, found {:?}",
+                key, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DezeroError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DezeroError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DezeroError {
+    fn from(err: std::io::Error) -> Self {
+        DezeroError::Io(err)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    array::Array,
+    array::{Array, Index},
     variable::{VBox, WeakVBox},
 };
 use std::{hash::Hash, rc::Rc};
@@ -11,7 +11,22 @@ pub fn call(mut f: impl Function + 'static, input: &[VBox]) -> VBox {
     f.set_inputs(input.into());
     f.set_output(output.clone().downgrade());
 
-    if *crate::ENABLE_BACKPROP.lock().unwrap() {
+    if input.iter().any(|x| x.get_tangent().is_some()) {
+        let x = input.iter().map(|i| i.get_array()).collect();
+        let t = input
+            .iter()
+            .map(|i| {
+                i.get_tangent()
+                    .unwrap_or_else(|| Array::zeros(&i.get_shape()))
+            })
+            .collect();
+        let ty = f
+            .jvp(x, t)
+            .expect("This function does not support forward-mode differentiation.");
+        output.set_tangent(ty);
+    }
+
+    if crate::config::enable_backprop() {
         f.set_generation(input.iter().map(|x| x.get_gen()).max().unwrap());
         let to_f = Rc::new(f);
         output.set_creator(FuncBox(to_f.clone()));
@@ -31,12 +46,22 @@ pub fn linear(x: &VBox, w: &VBox, b: Option<&VBox>) -> VBox {
 
 pub fn sigmoid(x: &VBox) -> VBox {
     let func = Sigmoid::new();
-    call(func, &[x.clone()])
+    call(func, std::slice::from_ref(x))
 }
 
 pub fn relu(x: &VBox) -> VBox {
     let func = ReLU::new();
-    call(func, &[x.clone()])
+    call(func, std::slice::from_ref(x))
+}
+
+pub fn dropout(x: &VBox, ratio: f32) -> VBox {
+    if crate::config::is_train() {
+        let mask = Array::rand(&x.get_shape()).gt(&Array::full(&[], ratio));
+        let func = Dropout::new(mask.astype::<f32>() / (1. - ratio));
+        call(func, std::slice::from_ref(x))
+    } else {
+        x.clone()
+    }
 }
 
 pub fn mean_squared_error(x: &VBox, y: &VBox) -> VBox {
@@ -46,7 +71,7 @@ pub fn mean_squared_error(x: &VBox, y: &VBox) -> VBox {
 
 pub fn softmax(x: &VBox, axis: usize) -> VBox {
     let func = Softmax::new(axis);
-    call(func, &[x.clone()])
+    call(func, std::slice::from_ref(x))
 }
 
 pub fn cross_entropy_loss(x: &VBox, t: &VBox) -> VBox {
@@ -54,7 +79,48 @@ pub fn cross_entropy_loss(x: &VBox, t: &VBox) -> VBox {
     call(func, &[x.clone(), t.clone()])
 }
 
+// Like `cross_entropy_loss`, but with the class index of each row of `x` in
+// place of one-hot targets.
+pub fn cross_entropy_loss_with_labels(x: &VBox, labels: &Array<i64>) -> VBox {
+    let shape = x.get_shape();
+    assert_eq!(
+        labels.get_shape()[..],
+        shape[..shape.len() - 1],
+        "cross_entropy_loss_with_labels: labels must have the shape of x without its last axis"
+    );
+    let t = VBox::new(labels.one_hot(shape[shape.len() - 1]));
+    cross_entropy_loss(x, &t)
+}
+
+pub fn concat(xs: &[VBox], axis: usize) -> VBox {
+    let sections = xs.iter().map(|x| x.get_shape()[axis]).collect();
+    let func = Concat::new(sections, axis);
+    call(func, xs)
+}
+
+pub fn stack(xs: &[VBox], axis: usize) -> VBox {
+    let expanded = xs
+        .iter()
+        .map(|x| {
+            let mut shape = x.get_shape();
+            shape.insert(axis, 1);
+            x.reshape(shape)
+        })
+        .collect::<Vec<_>>();
+    concat(&expanded, axis)
+}
+
+pub fn split(x: &VBox, sections: &[usize], axis: usize) -> Vec<VBox> {
+    (0..sections.len())
+        .map(|index| {
+            let func = Split::new(sections.to_vec(), axis, index);
+            call(func, std::slice::from_ref(x))
+        })
+        .collect()
+}
+
 pub trait Function {
+    fn name(&self) -> &'static str;
     fn get_generation(&self) -> u32;
     fn get_inputs(&self) -> Vec<VBox>;
     fn get_output(&self) -> WeakVBox;
@@ -64,11 +130,18 @@ pub trait Function {
     fn set_output(&mut self, output: WeakVBox);
 
     fn forward(&self, x: Vec<Array>) -> Array;
-    fn backward(&self, gy: Array) -> Vec<Array>;
+    fn backward(&self, gy: VBox) -> Vec<VBox>;
+    fn jvp(&self, _x: Vec<Array>, _t: Vec<Array>) -> Option<Array> {
+        None
+    }
 }
 
 macro_rules! impl_getters_setters {
     () => {
+        fn name(&self) -> &'static str {
+            std::any::type_name::<Self>().rsplit("::").next().unwrap()
+        }
+
         fn get_generation(&self) -> u32 {
             self.generation
         }
@@ -107,6 +180,7 @@ macro_rules! define {
         }
 
         impl $name {
+            #[allow(clippy::new_without_default)]
             pub fn new($($key: $type),*) -> Self {
                 Self {
                     inputs: None,
@@ -133,7 +207,7 @@ impl Function for Add {
     fn forward(&self, x: Vec<Array>) -> Array {
         &x[0] + &x[1]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
         let gx0 = gy.clone();
         let gx1 = gy;
         if self.shape0 != self.shape1 {
@@ -142,6 +216,9 @@ impl Function for Add {
             vec![gx0, gx1]
         }
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
 }
 
 define_binop!(Sub);
@@ -150,7 +227,7 @@ impl Function for Sub {
     fn forward(&self, x: Vec<Array>) -> Array {
         &x[0] - &x[1]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
         let gx0 = gy.clone();
         let gx1 = -gy;
         if self.shape0 != self.shape1 {
@@ -159,6 +236,9 @@ impl Function for Sub {
             vec![gx0, gx1]
         }
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
 }
 
 define_binop!(Mul);
@@ -167,14 +247,8 @@ impl Function for Mul {
     fn forward(&self, x: Vec<Array>) -> Array {
         &x[0] * &x[1]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
         let gx0 = &x[1] * &gy;
         let gx1 = &x[0] * gy;
         if self.shape0 != self.shape1 {
@@ -183,6 +257,9 @@ impl Function for Mul {
             vec![gx0, gx1]
         }
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(&t[0] * &x[1] + &x[0] * &t[1])
+    }
 }
 
 define_binop!(Div);
@@ -191,14 +268,8 @@ impl Function for Div {
     fn forward(&self, x: Vec<Array>) -> Array {
         &x[0] / &x[1]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
         let gx0 = &gy / &x[1];
         let gx1 = -&x[0] / (&x[1] * &x[1]) * gy;
         if self.shape0 != self.shape1 {
@@ -207,6 +278,9 @@ impl Function for Div {
             vec![gx0, gx1]
         }
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(&t[0] / &x[1] - &x[0] * &t[1] / (&x[1] * &x[1]))
+    }
 }
 
 define!(Neg,);
@@ -215,9 +289,12 @@ impl Function for Neg {
     fn forward(&self, x: Vec<Array>) -> Array {
         -x[0].clone()
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
         vec![-gy]
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
 }
 
 define!(Powi, n: i32);
@@ -226,10 +303,13 @@ impl Function for Powi {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].powi(self.n)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x = self.inputs.as_ref().unwrap().first().unwrap().get_array();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = &self.inputs.as_ref().unwrap()[0];
         vec![self.n as f32 * x.powi(self.n - 1) * gy]
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(x[0].powi(self.n - 1) * self.n as f32 * &t[0])
+    }
 }
 
 define!(Powf, c: f32);
@@ -238,9 +318,12 @@ impl Function for Powf {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].powf(self.c)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x = self.inputs.as_ref().unwrap().first().unwrap().get_array();
-        vec![self.c * x.powf(self.c - 1.) * gy]
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = &self.inputs.as_ref().unwrap()[0];
+        vec![self.c * x.pow(self.c - 1.) * gy]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(x[0].powf(self.c - 1.) * self.c * &t[0])
     }
 }
 
@@ -250,54 +333,219 @@ impl Function for Exp {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].exp()
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x = self.inputs.as_ref().unwrap().first().unwrap().get_array();
-        vec![x.exp() * gy]
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let y = self.output.as_ref().unwrap().upgrade();
+        vec![y * gy]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let y = self.output.as_ref().unwrap().get_array();
+        Some(y * &t[0])
     }
 }
 
+define!(Log,);
+impl Function for Log {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].ln()
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = &self.inputs.as_ref().unwrap()[0];
+        vec![gy / x]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(&t[0] / &x[0])
+    }
+}
+
+define!(Sin,);
+impl Function for Sin {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].sin()
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = &self.inputs.as_ref().unwrap()[0];
+        vec![x.cos() * gy]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(x[0].cos() * &t[0])
+    }
+}
+
+define!(Cos,);
+impl Function for Cos {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].cos()
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = &self.inputs.as_ref().unwrap()[0];
+        vec![-x.sin() * gy]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(-(x[0].sin() * &t[0]))
+    }
+}
+
+define!(Tanh,);
+impl Function for Tanh {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].tanh()
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let y = self.output.as_ref().unwrap().upgrade();
+        vec![gy * (1. - &y * &y)]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let y = self.output.as_ref().unwrap().get_array();
+        Some((1. - &y * &y) * &t[0])
+    }
+}
+
+define!(Clip, lowerbound: f32, upperbound: f32);
+impl Function for Clip {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].clip(self.lowerbound, self.upperbound)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap()[0].get_array();
+        let mask = clip_mask(&x, self.lowerbound, self.upperbound);
+        vec![gy * VBox::new(mask)]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(&t[0] * clip_mask(&x[0], self.lowerbound, self.upperbound))
+    }
+}
+
+fn clip_mask(x: &Array, lowerbound: f32, upperbound: f32) -> Array {
+    let lower = x.ge(&Array::full(&[], lowerbound));
+    let upper = x.le(&Array::full(&[], upperbound));
+    lower.astype::<f32>() * upper.astype::<f32>()
+}
+
 define!(Reshape, shape_in: Vec<usize>, shape_out: Vec<usize>);
 impl Function for Reshape {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].clone().reshape(&self.shape_out)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        vec![gy.reshape(&self.shape_in)]
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        vec![gy.reshape(self.shape_in.clone())]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
     }
 }
 
-define!(Transpose,);
+define!(Transpose, axes: Vec<usize>);
 impl Function for Transpose {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
-        x[0].clone().transpose()
+        x[0].permute(&self.axes)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let mut inv = vec![0; self.axes.len()];
+        for (i, &axis) in self.axes.iter().enumerate() {
+            inv[axis] = i;
+        }
+        vec![gy.transpose_axes(&inv)]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        vec![gy.transpose()]
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
     }
 }
 
-define!(Sum, shape: Vec<usize>);
+define!(Sum, axes: Option<Vec<usize>>, keepdims: bool, shape: Vec<usize>);
 impl Function for Sum {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
-        x[0].sum()
+        x[0].sum(self.axes.as_deref(), self.keepdims)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let shape = keepdims_shape(&self.shape, self.axes.as_deref());
+        vec![gy.reshape(shape).broadcast_to(&self.shape)]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(Mean, axes: Option<Vec<usize>>, keepdims: bool, shape: Vec<usize>);
+impl Function for Mean {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].mean(self.axes.as_deref(), self.keepdims)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let shape = keepdims_shape(&self.shape, self.axes.as_deref());
+        let count = self.shape.iter().product::<usize>() / shape.iter().product::<usize>();
+        vec![gy.reshape(shape).broadcast_to(&self.shape) / count as f32]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(Max, axes: Option<Vec<usize>>, keepdims: bool);
+impl Function for Max {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].max(self.axes.as_deref(), self.keepdims)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap()[0].get_array();
+        let shape = keepdims_shape(x.get_shape(), self.axes.as_deref());
+        vec![gy.reshape(shape) * VBox::new(x.argmax_mask(self.axes.as_deref()))]
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        vec![gy.broadcast_to(&self.shape)]
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let mask = x[0].argmax_mask(self.axes.as_deref());
+        Some((&t[0] * mask).sum(self.axes.as_deref(), self.keepdims))
     }
 }
 
+define!(Min, axes: Option<Vec<usize>>, keepdims: bool);
+impl Function for Min {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].min(self.axes.as_deref(), self.keepdims)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap()[0].get_array();
+        let shape = keepdims_shape(x.get_shape(), self.axes.as_deref());
+        vec![gy.reshape(shape) * VBox::new(x.argmin_mask(self.axes.as_deref()))]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let mask = x[0].argmin_mask(self.axes.as_deref());
+        Some((&t[0] * mask).sum(self.axes.as_deref(), self.keepdims))
+    }
+}
+
+fn keepdims_shape(shape: &[usize], axes: Option<&[usize]>) -> Vec<usize> {
+    shape
+        .iter()
+        .enumerate()
+        .map(|(axis, &n)| match axes {
+            Some(axes) if !axes.contains(&axis) => n,
+            _ => 1,
+        })
+        .collect()
+}
+
 define!(SumTo, shape_in: Vec<usize>, shape_out: Vec<usize>);
 impl Function for SumTo {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].clone().sum_to(&self.shape_out)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
         vec![gy.broadcast_to(&self.shape_in)]
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
 }
 
 define!(BroadcastTo, shape_in: Vec<usize>, shape_out: Vec<usize>);
@@ -306,9 +554,84 @@ impl Function for BroadcastTo {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].clone().broadcast_to(&self.shape_out)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
         vec![gy.sum_to(&self.shape_in)]
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(GetItem, slices: Vec<Index>, shape_in: Vec<usize>);
+impl Function for GetItem {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].slice(&self.slices)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let f = GetItemGrad::new(self.slices.clone(), self.shape_in.clone());
+        vec![call(f, &[gy])]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(GetItemGrad, slices: Vec<Index>, shape_in: Vec<usize>);
+impl Function for GetItemGrad {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        Array::zeros(&self.shape_in).add_at(&self.slices, &x[0])
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        vec![gy.get_item(&self.slices)]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(Concat, sections: Vec<usize>, axis: usize);
+impl Function for Concat {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        Array::concat(&x.iter().collect::<Vec<_>>(), self.axis)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        split(&gy, &self.sections, self.axis)
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
+}
+
+define!(Split, sections: Vec<usize>, axis: usize, index: usize);
+impl Function for Split {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].split(&self.sections, self.axis)
+            .swap_remove(self.index)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let mut shape = gy.get_shape();
+        let parts = self
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(i, &len)| {
+                if i == self.index {
+                    gy.clone()
+                } else {
+                    shape[self.axis] = len;
+                    VBox::new(Array::zeros(&shape))
+                }
+            })
+            .collect::<Vec<_>>();
+        vec![concat(&parts, self.axis)]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
+    }
 }
 
 define!(Matmul,);
@@ -317,19 +640,51 @@ impl Function for Matmul {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].matmul(&x[1])
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
+        let (shape0, shape1) = (x[0].get_shape(), x[1].get_shape());
+        if shape0.len() == 2 && shape1.len() == 2 {
+            return vec![gy.matmul(&x[1].transpose()), x[0].transpose().matmul(&gy)];
+        }
+
+        // 1-D operands are promoted to matrices as in the forward pass.
+        let mut gy = gy;
+        let x0 = match shape0.len() {
+            1 => x[0].reshape(vec![1, shape0[0]]),
+            _ => x[0].clone(),
+        };
+        let x1 = match shape1.len() {
+            1 => x[1].reshape(vec![shape1[0], 1]),
+            _ => x[1].clone(),
+        };
+        if shape1.len() == 1 {
+            let mut shape = gy.get_shape();
+            shape.push(1);
+            gy = gy.reshape(shape);
+        }
+        if shape0.len() == 1 {
+            let mut shape = gy.get_shape();
+            shape.insert(shape.len() - 1, 1);
+            gy = gy.reshape(shape);
+        }
+
+        let gx0 = gy.matmul(&matrix_transpose(&x1));
+        let gx1 = matrix_transpose(&x0).matmul(&gy);
         vec![
-            gy.matmul(&x[1].clone().transpose()),
-            x[0].clone().transpose().matmul(&gy),
+            gx0.sum_to(&x0.get_shape()).reshape(shape0),
+            gx1.sum_to(&x1.get_shape()).reshape(shape1),
         ]
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(t[0].matmul(&x[1]) + x[0].matmul(&t[1]))
+    }
+}
+
+fn matrix_transpose(x: &VBox) -> VBox {
+    let dim = x.get_shape().len();
+    let mut axes = (0..dim).collect::<Vec<_>>();
+    axes.swap(dim - 2, dim - 1);
+    x.transpose_axes(&axes)
 }
 
 define!(Linear, bias: bool);
@@ -343,22 +698,24 @@ impl Function for Linear {
             t
         }
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
-        let gx = gy.matmul(&x[1].clone().transpose());
-        let gw = x[0].clone().transpose().matmul(&gy);
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
+        let gx = gy.matmul(&x[1].transpose());
+        let gw = x[0].transpose().matmul(&gy);
         if self.bias {
             vec![gx, gw, gy.sum_to(&x[2].get_shape())]
         } else {
             vec![gx, gw]
         }
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let ty = t[0].matmul(&x[1]) + x[0].matmul(&t[1]);
+        if self.bias {
+            Some(ty + &t[2])
+        } else {
+            Some(ty)
+        }
+    }
 }
 
 define!(Sigmoid,);
@@ -367,10 +724,14 @@ impl Function for Sigmoid {
     fn forward(&self, x: Vec<Array>) -> Array {
         (&x[0] * 0.5).tanh() * 0.5 + 0.5
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let y = self.output.as_ref().unwrap().get_array();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let y = self.output.as_ref().unwrap().upgrade();
         vec![gy * &y * (1. - y)]
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let y = self.output.as_ref().unwrap().get_array();
+        Some(&t[0] * &y * (1. - &y))
+    }
 }
 
 define!(ReLU,);
@@ -379,9 +740,28 @@ impl Function for ReLU {
     fn forward(&self, x: Vec<Array>) -> Array {
         x[0].relu_max(0.)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x = self.inputs.as_ref().unwrap().first().unwrap().get_array();
-        vec![x.relu_mask(&gy, 0.)]
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap()[0].get_array();
+        let mask = x.gt(&Array::full(&[], 0.)).astype::<f32>();
+        vec![gy * VBox::new(mask)]
+    }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let mask = x[0].gt(&Array::full(&[], 0.)).astype::<f32>();
+        Some(&t[0] * mask)
+    }
+}
+
+define!(Dropout, mask: Array);
+impl Function for Dropout {
+    impl_getters_setters!();
+    fn forward(&self, x: Vec<Array>) -> Array {
+        &x[0] * &self.mask
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        vec![gy * VBox::new(self.mask.clone())]
+    }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        Some(self.forward(t))
     }
 }
 
@@ -390,62 +770,76 @@ impl Function for MeanSquaredError {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
         let diff = &x[0] - &x[1];
-        diff.powi(2).sum() / diff.size() as f32
+        diff.powi(2).mean(None, false)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
         let diff = &x[0] - &x[1];
-        let gx = gy * &diff * (2. / diff.size() as f32);
+        let size = diff.get_array().size();
+        let gx = gy * &diff * (2. / size as f32);
         vec![gx.clone(), -gx]
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let diff = &x[0] - &x[1];
+        Some((diff * (&t[0] - &t[1]) * 2.).mean(None, false))
+    }
 }
 
 define!(Softmax, axis: usize);
 impl Function for Softmax {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
-        let y = (&x[0] - x[0].max(self.axis)).exp();
-        &y / y.sum_with_axis(self.axis)
+        let axes = Some(&[self.axis][..]);
+        let y = (&x[0] - x[0].max(axes, true)).exp();
+        &y / y.sum(axes, true)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let y = self.output.as_ref().unwrap().get_array();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let y = self.output.as_ref().unwrap().upgrade();
         let gx = &y * gy;
-        let sumdx = &gx.sum_with_axis(self.axis);
+        let sumdx = &gx.sum(Some(&[self.axis]), true);
         vec![gx - y * sumdx]
     }
+    fn jvp(&self, _x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let y = self.output.as_ref().unwrap().get_array();
+        let yt = &y * &t[0];
+        let sum = yt.sum(Some(&[self.axis]), true);
+        Some(yt - y * sum)
+    }
 }
 
 define!(CrossEnrtopy,);
 impl Function for CrossEnrtopy {
     impl_getters_setters!();
     fn forward(&self, x: Vec<Array>) -> Array {
-        -x[0].clip(1e-15, 1.).ln().matmul(&x[1].transpose()).sum()
+        -(&x[1] * &x[0].clip(1e-15, 1.).ln()).sum(None, false)
     }
-    fn backward(&self, gy: Array) -> Vec<Array> {
-        let x: Vec<Array> = self
-            .inputs
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|x| x.get_array())
-            .collect();
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        let x = self.inputs.as_ref().unwrap();
         let cliped_x = &x[0].clip(1e-15, 1.);
         let gx = -&x[1] / cliped_x;
         let gt = -cliped_x.ln();
         vec![gx * &gy, gt * &gy]
     }
+    fn jvp(&self, x: Vec<Array>, t: Vec<Array>) -> Option<Array> {
+        let cliped_x = x[0].clip(1e-15, 1.);
+        let tx = &t[0] / &cliped_x * &x[1];
+        let tt = cliped_x.ln() * &t[1];
+        Some(-(tx + tt).sum(None, false))
+    }
 }
 
 #[derive(Clone)]
 pub struct FuncBox(Rc<dyn Function>);
 
 impl FuncBox {
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as *const () as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
     pub fn get_gen(&self) -> u32 {
         self.0.get_generation()
     }
@@ -458,7 +852,7 @@ impl FuncBox {
         self.0.get_output()
     }
 
-    pub fn backward(&self, gy: Array) -> Vec<Array> {
+    pub fn backward(&self, gy: VBox) -> Vec<VBox> {
         self.0.backward(gy)
     }
 }
@@ -1,8 +1,11 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::Path, rc::Rc};
 
 use crate::functions as F;
 use crate::{
     array::Array,
+    error::{DezeroError, Result},
+    safetensors,
+    state_dict::{self, StateDict},
     variable::{VBox, WeakVBox},
 };
 
@@ -25,6 +28,55 @@ impl Model {
     pub fn get_params(&self) -> Vec<VBox> {
         self.0.borrow().get_params()
     }
+
+    pub fn named_params(&self) -> Vec<(String, VBox)> {
+        self.0.borrow().named_params()
+    }
+
+    pub fn state_dict(&self) -> StateDict {
+        self.named_params()
+            .into_iter()
+            .map(|(name, param)| (name, param.get_array()))
+            .collect()
+    }
+
+    // Every key and shape is checked before any param is written, so a failed
+    // load leaves the model unchanged.
+    pub fn load_state_dict(&self, state_dict: &StateDict) -> Result<()> {
+        for name in self.0.borrow().param_names() {
+            if !state_dict.contains_key(&name) {
+                return Err(DezeroError::MissingKey(name));
+            }
+        }
+        for (name, array) in state_dict {
+            self.0.borrow().check_param(name, array)?;
+        }
+        for (name, array) in state_dict {
+            self.0.borrow_mut().load_param(name, array)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_weights(&self, path: impl AsRef<Path>) -> Result<()> {
+        state_dict::save(path, &self.state_dict())
+    }
+
+    pub fn load_weights(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.load_state_dict(&state_dict::load(path)?)
+    }
+
+    pub fn save_safetensors(&self, path: impl AsRef<Path>) -> Result<()> {
+        safetensors::save(path, &self.state_dict())
+    }
+
+    pub fn load_safetensors(&self, path: impl AsRef<Path>) -> Result<()> {
+        let state_dict = safetensors::load(path)?;
+        if safetensors::is_torch(&state_dict) {
+            self.load_state_dict(&safetensors::from_torch(state_dict))
+        } else {
+            self.load_state_dict(&state_dict)
+        }
+    }
 }
 
 pub trait Layer {
@@ -37,6 +89,11 @@ pub trait Layer {
     fn clear_grads(&mut self);
     fn set_io(&mut self, input: &VBox, output: &VBox);
     fn get_params(&self) -> Vec<VBox>;
+    fn named_params(&self) -> Vec<(String, VBox)>;
+    // Names of all params, including those not created yet.
+    fn param_names(&self) -> Vec<String>;
+    fn check_param(&self, name: &str, array: &Array) -> Result<()>;
+    fn load_param(&mut self, name: &str, array: &Array) -> Result<()>;
 }
 
 pub struct MLP {
@@ -65,6 +122,15 @@ impl MLP {
     pub fn get_out_sizes(&self) -> Vec<usize> {
         self.out_sizes.to_vec()
     }
+
+    // Splits `layers.<i>.<name>` into the layer index and the name within it.
+    fn split_key<'a>(&self, key: &'a str) -> Result<(usize, &'a str)> {
+        key.strip_prefix("layers.")
+            .and_then(|rest| rest.split_once('.'))
+            .and_then(|(i, rest)| Some((i.parse::<usize>().ok()?, rest)))
+            .filter(|(i, _)| *i < self.layers.len())
+            .ok_or_else(|| DezeroError::UnexpectedKey(key.to_string()))
+    }
 }
 
 impl Layer for MLP {
@@ -87,6 +153,36 @@ impl Layer for MLP {
         }
         params
     }
+    fn named_params(&self) -> Vec<(String, VBox)> {
+        let mut params = Vec::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            for (name, param) in layer.named_params() {
+                params.push((format!("layers.{}.{}", i, name), param));
+            }
+        }
+        params
+    }
+    fn param_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            for name in layer.param_names() {
+                names.push(format!("layers.{}.{}", i, name));
+            }
+        }
+        names
+    }
+    fn check_param(&self, name: &str, array: &Array) -> Result<()> {
+        let (i, rest) = self.split_key(name)?;
+        self.layers[i]
+            .check_param(rest, array)
+            .map_err(|e| rename_key(e, name))
+    }
+    fn load_param(&mut self, name: &str, array: &Array) -> Result<()> {
+        let (i, rest) = self.split_key(name)?;
+        self.layers[i]
+            .load_param(rest, array)
+            .map_err(|e| rename_key(e, name))
+    }
     fn clear_grads(&mut self) {
         for layer in &mut self.layers {
             layer.clear_grads();
@@ -105,7 +201,7 @@ pub struct Linear {
 impl Linear {
     pub fn new(out_size: usize, bias: bool) -> Self {
         let b = if bias {
-            Some(VBox::new(Array::zeros(&[out_size])))
+            Some(VBox::new(Array::zeros(&[out_size])).with_name("b"))
         } else {
             None
         };
@@ -124,7 +220,8 @@ impl Linear {
             &[in_size, self.out_size],
             0.,
             (in_size as f32).recip(),
-        ));
+        ))
+        .with_name("w");
         self.w = Some(w);
     }
 }
@@ -134,7 +231,7 @@ impl Layer for Linear {
         if self.w.is_none() {
             self.init_w(x.get_shape()[1]);
         }
-        F::linear(&x, self.w.as_ref().unwrap(), self.b.as_ref())
+        F::linear(x, self.w.as_ref().unwrap(), self.b.as_ref())
     }
     fn clear_grads(&mut self) {
         self.w.as_ref().unwrap().clear_grad();
@@ -156,4 +253,68 @@ impl Layer for Linear {
         }
         params
     }
+    fn named_params(&self) -> Vec<(String, VBox)> {
+        let mut params = Vec::new();
+        if let Some(w) = self.w.as_ref() {
+            params.push(("w".to_string(), w.clone()));
+        }
+        if let Some(b) = self.b.as_ref() {
+            params.push(("b".to_string(), b.clone()))
+        }
+        params
+    }
+    fn param_names(&self) -> Vec<String> {
+        let mut names = vec!["w".to_string()];
+        if self.b.is_some() {
+            names.push("b".to_string());
+        }
+        names
+    }
+    fn check_param(&self, name: &str, array: &Array) -> Result<()> {
+        let shape = array.get_shape();
+        let expected = match (name, &self.w, &self.b) {
+            ("w", Some(w), _) => w.get_shape(),
+            ("w", None, _) => vec![shape.first().copied().unwrap_or(0), self.out_size],
+            ("b", _, Some(b)) => b.get_shape(),
+            _ => return Err(DezeroError::UnexpectedKey(name.to_string())),
+        };
+        check_shape(name, &expected, shape)
+    }
+    fn load_param(&mut self, name: &str, array: &Array) -> Result<()> {
+        self.check_param(name, array)?;
+        if name == "w" && self.w.is_none() {
+            self.init_w(array.get_shape()[0]);
+        }
+        let param = match name {
+            "w" => self.w.as_ref(),
+            _ => self.b.as_ref(),
+        };
+        param.unwrap().set_array(array.clone());
+        Ok(())
+    }
+}
+
+fn rename_key(e: DezeroError, key: &str) -> DezeroError {
+    match e {
+        DezeroError::UnexpectedKey(_) => DezeroError::UnexpectedKey(key.to_string()),
+        DezeroError::ParamShape {
+            expected, found, ..
+        } => DezeroError::ParamShape {
+            key: key.to_string(),
+            expected,
+            found,
+        },
+        e => e,
+    }
+}
+
+fn check_shape(key: &str, expected: &[usize], found: &[usize]) -> Result<()> {
+    if expected != found {
+        return Err(DezeroError::ParamShape {
+            key: key.to_string(),
+            expected: expected.to_vec(),
+            found: found.to_vec(),
+        });
+    }
+    Ok(())
 }
@@ -1,10 +1,16 @@
 pub mod array;
+pub mod autograd;
+pub mod config;
+pub mod dot;
+pub mod error;
 pub mod functions;
 pub mod layers;
 mod macros;
 pub mod optimizers;
+pub mod safetensors;
+pub mod schedulers;
+pub mod state_dict;
 pub mod variable;
 
-use std::sync::Mutex;
-
-pub static ENABLE_BACKPROP: Mutex<bool> = Mutex::new(true);
+pub use config::{no_grad, test_mode, using_config};
+pub use error::DezeroError;
@@ -1,13 +1,15 @@
 #[macro_export]
 macro_rules! eval {
     () => {
-        *$crate::ENABLE_BACKPROP.lock().unwrap() = false;
+        let _no_grad = $crate::config::no_grad();
+        let _test_mode = $crate::config::test_mode();
     };
 }
 
 #[macro_export]
 macro_rules! train {
     () => {
-        *$crate::ENABLE_BACKPROP.lock().unwrap() = true;
+        let _enable_backprop = $crate::config::using_config("enable_backprop", true);
+        let _train = $crate::config::using_config("train", true);
     };
 }
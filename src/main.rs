@@ -1,28 +1,42 @@
 use dezero::{
-    array::Array,
+    array::{Array, CsvOptions},
     layers::Model,
     layers::MLP,
     optimizers::{Momentum, Optimizer},
     variable::VBox,
 };
-use dezero::{eval, functions as F};
+use dezero::{eval, functions as F, s};
+use rand::seq::SliceRandom;
 
 fn main() {
-    let (vec_x, vec_t) = load_mnist("mnist_test.csv");
+    let (data_x, data_t) = load_mnist("mnist_test.csv");
+    let data_size = data_x.get_shape()[0];
+    let batch_size = 100;
 
     let model = Model::new(MLP::new(&[100, 10], Box::new(F::relu)));
 
-    let mut optimizer = Momentum::new(0.1, 0.9, model.clone());
+    let weights_path = "mnist_weights.bin";
+    let epochs = if std::path::Path::new(weights_path).exists() {
+        model
+            .load_weights(weights_path)
+            .expect("Failed to load weights");
+        0
+    } else {
+        10
+    };
 
-    let epochs = 10;
+    let mut optimizer = Momentum::new(0.1, 0.9, model.clone());
 
     for i in 0..epochs {
+        let mut index = (0..data_size).collect::<Vec<_>>();
+        index.shuffle(&mut rand::thread_rng());
+
         let mut loss_tot = 0.;
-        for (x, t) in vec_x.iter().zip(vec_t.iter()) {
-            let x = &VBox::new(x.clone());
-            let t = &VBox::new(t.clone());
+        for batch_index in index.chunks(batch_size) {
+            let x = &VBox::new(data_x.take(batch_index, 0));
+            let t = &data_t.take(batch_index, 0);
             let y = &F::softmax(&model.call(x), 1);
-            let loss = &F::cross_entropy_loss(y, t);
+            let loss = &F::cross_entropy_loss_with_labels(y, t);
 
             loss_tot += loss.get_array().get_data()[0];
 
@@ -35,41 +49,23 @@ fn main() {
         println!("loss: {loss_tot}");
     }
 
+    if epochs > 0 {
+        model
+            .save_weights(weights_path)
+            .expect("Failed to save weights");
+    }
+
     eval!();
-    let x = &VBox::new(vec_x[0].clone());
+    let x = &VBox::new(data_x.slice(&s![..batch_size]));
     let y = &F::softmax(&model.call(x), 1);
     y.get_array().write_csv("mnist_res_test.csv");
 }
 
-fn load_mnist(path: &str) -> (Vec<Array>, Vec<Array>) {
+fn load_mnist(path: &str) -> (Array, Array<i64>) {
     println!("loading...");
-    let f = std::fs::read_to_string(path).expect("File not found");
-    let lines = f.lines().collect::<Vec<_>>();
-    let num_cols = lines[0].split(',').count() - 1;
-
-    let mut vec_data = Vec::new();
-    let mut vec_target = Vec::new();
-
-    for minibatch in lines.chunks(100) {
-        let num_rows = minibatch.len();
-        let mut data = Vec::new();
-        let mut target = Vec::new();
-        for line in minibatch {
-            let line = line
-                .split(',')
-                .map(|d| d.parse::<f32>().unwrap())
-                .collect::<Vec<_>>();
-            let mut target_row = vec![0.; 10];
-            target_row[line[0] as usize] = 1.;
-            data.append(&mut line[1..].to_vec());
-            target.append(&mut target_row);
-        }
-        let x = Array::new(data, vec![num_rows, num_cols]);
-        let t = Array::new(target, vec![num_rows, 10]);
-        vec_data.push(x);
-        vec_target.push(t);
-    }
-
+    let options = CsvOptions::new().label_column(0);
+    let (data, labels) =
+        Array::try_read_csv_with(path, &options).unwrap_or_else(|e| panic!("{}: {}", path, e));
     println!("load finished");
-    (vec_data, vec_target)
+    (data, labels.unwrap().astype())
 }
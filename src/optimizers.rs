@@ -1,68 +1,837 @@
-use std::collections::HashMap;
+mod lbfgs;
 
-use crate::{array::Array, layers::Model, variable::VBox};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    array::Array,
+    error::{DezeroError, Result},
+    layers::Model,
+    state_dict::StateDict,
+    variable::VBox,
+};
+pub use lbfgs::{LineSearch, LBFGS};
 
 pub trait Optimizer {
     fn update(&mut self) {
         let params = self.get_params();
+        self.run_hooks(&params);
+
+        for (param, options) in self.param_groups().resolve() {
+            self.update_one(param, &options)
+        }
+    }
+    fn param_groups(&self) -> &ParamGroups;
+    fn param_groups_mut(&mut self) -> &mut ParamGroups;
+    fn get_params(&self) -> Vec<VBox> {
+        self.param_groups().params()
+    }
+    fn add_param_group(&mut self, group: ParamGroup) {
+        self.param_groups_mut().groups.push(group);
+        self.param_groups().check_disjoint();
+    }
+    // A plain gradient step; optimizers with per-param state override it.
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        param.set_array(param.get_array() - options.lr * decayed_grad(&param, options))
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>>;
+    fn add_hook(&mut self, hook: Box<dyn Hook>) {
+        self.hooks().push(hook)
+    }
+    fn run_hooks(&mut self, params: &[VBox]) {
+        for hook in self.hooks() {
+            hook.apply(params)
+        }
+    }
+    fn lr(&self) -> f32 {
+        self.param_groups().defaults.lr
+    }
+    // Groups with their own lr are rescaled so that their ratio to the
+    // optimizer's lr stays fixed, which lets schedulers drive every group.
+    fn set_lr(&mut self, lr: f32) {
+        self.param_groups_mut().defaults.lr = lr
+    }
+    // Per-param state such as moments, saved as `<slot>.<index into get_params()>`.
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        Vec::new()
+    }
+    fn state_dict(&mut self) -> StateDict {
+        save_state(self)
+    }
+    fn load_state_dict(&mut self, state_dict: &StateDict) -> Result<()> {
+        load_state(self, state_dict, &[])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GroupOptions {
+    pub lr: f32,
+    pub momentum: f32,
+    pub weight_decay: f32,
+}
+
+enum Params {
+    Model(Model),
+    Vars(Vec<VBox>),
+}
+
+// A set of params with optional overrides of the optimizer's hyperparameters.
+// `weight_decay` is applied as L2 regularization, except by `AdamW`.
+pub struct ParamGroup {
+    params: Params,
+    lr: Option<f32>,
+    momentum: Option<f32>,
+    weight_decay: Option<f32>,
+}
+
+impl ParamGroup {
+    pub fn new(params: Vec<VBox>) -> Self {
+        ParamGroup {
+            params: Params::Vars(params),
+            lr: None,
+            momentum: None,
+            weight_decay: None,
+        }
+    }
+
+    // Params of a model are looked up on every update, so layers that create
+    // them lazily can be passed before the first forward pass.
+    pub fn from_model(model: Model) -> Self {
+        ParamGroup {
+            params: Params::Model(model),
+            ..ParamGroup::new(Vec::new())
+        }
+    }
+
+    pub fn lr(mut self, lr: f32) -> Self {
+        self.lr = Some(lr);
+        self
+    }
+
+    pub fn momentum(mut self, momentum: f32) -> Self {
+        self.momentum = Some(momentum);
+        self
+    }
+
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self {
+        self.weight_decay = Some(weight_decay);
+        self
+    }
+
+    pub fn params(&self) -> Vec<VBox> {
+        match &self.params {
+            Params::Model(model) => model.get_params(),
+            Params::Vars(params) => params.clone(),
+        }
+    }
+}
+
+pub trait IntoParamGroups {
+    fn into_param_groups(self) -> Vec<ParamGroup>;
+}
+
+impl IntoParamGroups for Model {
+    fn into_param_groups(self) -> Vec<ParamGroup> {
+        vec![ParamGroup::from_model(self)]
+    }
+}
+
+impl IntoParamGroups for Vec<VBox> {
+    fn into_param_groups(self) -> Vec<ParamGroup> {
+        vec![ParamGroup::new(self)]
+    }
+}
+
+impl IntoParamGroups for ParamGroup {
+    fn into_param_groups(self) -> Vec<ParamGroup> {
+        vec![self]
+    }
+}
+
+impl IntoParamGroups for Vec<ParamGroup> {
+    fn into_param_groups(self) -> Vec<ParamGroup> {
+        self
+    }
+}
+
+pub struct ParamGroups {
+    defaults: GroupOptions,
+    base_lr: f32,
+    groups: Vec<ParamGroup>,
+}
+
+impl ParamGroups {
+    fn new(defaults: GroupOptions, params: impl IntoParamGroups) -> Self {
+        let groups = ParamGroups {
+            defaults,
+            base_lr: defaults.lr,
+            groups: params.into_param_groups(),
+        };
+        groups.check_disjoint();
+        groups
+    }
+
+    // A param listed twice would be updated twice per step. Params of a model
+    // are created lazily, so this is checked again on every update.
+    #[allow(clippy::mutable_key_type)]
+    fn check_disjoint(&self) {
+        let mut seen = HashSet::new();
+        for param in self.params() {
+            assert!(
+                seen.insert(param),
+                "a param cannot appear more than once in the param groups"
+            );
+        }
+    }
+
+    pub fn groups(&self) -> &[ParamGroup] {
+        &self.groups
+    }
+
+    pub fn params(&self) -> Vec<VBox> {
+        self.groups.iter().flat_map(ParamGroup::params).collect()
+    }
 
+    pub fn options(&self, group: &ParamGroup) -> GroupOptions {
+        let scale = match self.base_lr {
+            0. => 1.,
+            base_lr => self.defaults.lr / base_lr,
+        };
+        GroupOptions {
+            lr: group.lr.map_or(self.defaults.lr, |lr| lr * scale),
+            momentum: group.momentum.unwrap_or(self.defaults.momentum),
+            weight_decay: group.weight_decay.unwrap_or(self.defaults.weight_decay),
+        }
+    }
+
+    // Params that have a grad, with the options of their group.
+    fn resolve(&self) -> Vec<(VBox, GroupOptions)> {
+        self.check_disjoint();
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                let options = self.options(group);
+                group
+                    .params()
+                    .into_iter()
+                    .filter(|param| param.get_option_grad().is_some())
+                    .map(move |param| (param, options))
+            })
+            .collect()
+    }
+}
+
+fn decayed_grad(param: &VBox, options: &GroupOptions) -> Array {
+    let grad = param.get_grad();
+    if options.weight_decay == 0. {
+        grad
+    } else {
+        grad + options.weight_decay * param.get_array()
+    }
+}
+
+fn save_state<O: Optimizer + ?Sized>(optimizer: &mut O) -> StateDict {
+    let params = optimizer.get_params();
+    let mut state_dict = StateDict::new();
+    state_dict.insert("lr".to_string(), scalar(optimizer.lr()));
+    for (name, slot) in optimizer.slots() {
+        for (i, param) in params.iter().enumerate() {
+            if let Some(array) = slot.get(param) {
+                state_dict.insert(format!("{}.{}", name, i), array.clone());
+            }
+        }
+    }
+    state_dict
+}
+
+// Replaces all per-param state; keys listed in `scalars` are left to the caller.
+fn load_state<O: Optimizer + ?Sized>(
+    optimizer: &mut O,
+    state_dict: &StateDict,
+    scalars: &[&str],
+) -> Result<()> {
+    let params = optimizer.get_params();
+    let names = optimizer
+        .slots()
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    let lr = get_scalar(state_dict, "lr")?;
+
+    let mut entries = Vec::new();
+    for (key, array) in state_dict {
+        if key == "lr" || scalars.contains(&key.as_str()) {
+            continue;
+        }
+        let (name, param) = key
+            .split_once('.')
+            .and_then(|(name, i)| Some((name, params.get(i.parse::<usize>().ok()?)?)))
+            .filter(|(name, _)| names.contains(name))
+            .ok_or_else(|| DezeroError::UnexpectedKey(key.clone()))?;
+        if *array.get_shape() != param.get_shape() {
+            return Err(DezeroError::ParamShape {
+                key: key.clone(),
+                expected: param.get_shape(),
+                found: array.get_shape().clone(),
+            });
+        }
+        entries.push((name, param.clone(), array.clone()));
+    }
+
+    let mut slots = optimizer.slots();
+    for (_, slot) in slots.iter_mut() {
+        slot.clear();
+    }
+    for (name, param, array) in entries {
+        if let Some((_, slot)) = slots.iter_mut().find(|(n, _)| *n == name) {
+            slot.insert(param, array);
+        }
+    }
+    optimizer.set_lr(lr);
+    Ok(())
+}
+
+fn scalar(x: f32) -> Array {
+    Array::new(vec![x], vec![])
+}
+
+fn get_scalar(state_dict: &StateDict, key: &str) -> Result<f32> {
+    let array = state_dict
+        .get(key)
+        .ok_or_else(|| DezeroError::MissingKey(key.to_string()))?;
+    match array.to_vec()[..] {
+        [x] => Ok(x),
+        _ => Err(DezeroError::ParamShape {
+            key: key.to_string(),
+            expected: vec![],
+            found: array.get_shape().clone(),
+        }),
+    }
+}
+
+// Hooks run on all params before `update_one`, e.g. to modify their grads.
+pub trait Hook {
+    fn apply(&mut self, params: &[VBox]);
+}
+
+impl<F: FnMut(&[VBox])> Hook for F {
+    fn apply(&mut self, params: &[VBox]) {
+        self(params)
+    }
+}
+
+// L2 regularization: adds `rate * param` to each grad.
+pub struct WeightDecay {
+    rate: f32,
+}
+
+impl WeightDecay {
+    pub fn new(rate: f32) -> Self {
+        WeightDecay { rate }
+    }
+}
+
+impl Hook for WeightDecay {
+    fn apply(&mut self, params: &[VBox]) {
         for param in params {
-            self.update_one(param)
+            if let Some(grad) = param.get_option_grad() {
+                param.set_grad(grad + self.rate * param.get_array());
+            }
         }
     }
-    fn get_params(&mut self) -> Vec<VBox>;
-    fn update_one(&mut self, param: VBox);
+}
+
+pub struct ClipGrad {
+    max_norm: f32,
+}
+
+impl ClipGrad {
+    pub fn new(max_norm: f32) -> Self {
+        ClipGrad { max_norm }
+    }
+}
+
+impl Hook for ClipGrad {
+    fn apply(&mut self, params: &[VBox]) {
+        clip_grad_norm(params, self.max_norm);
+    }
+}
+
+pub struct ClipGradValue {
+    clip_value: f32,
+}
+
+impl ClipGradValue {
+    pub fn new(clip_value: f32) -> Self {
+        ClipGradValue { clip_value }
+    }
+}
+
+impl Hook for ClipGradValue {
+    fn apply(&mut self, params: &[VBox]) {
+        for param in params {
+            if let Some(grad) = param.get_option_grad() {
+                param.set_grad(grad.clip(-self.clip_value, self.clip_value));
+            }
+        }
+    }
+}
+
+// Rescales all grads so that their global L2 norm is at most `max_norm` and
+// returns the norm before clipping.
+pub fn clip_grad_norm(params: &[VBox], max_norm: f32) -> f32 {
+    let total_norm = params
+        .iter()
+        .filter_map(|param| param.get_option_grad())
+        .map(|grad| grad.iter().map(|x| x * x).sum::<f32>())
+        .sum::<f32>()
+        .sqrt();
+    let rate = max_norm / (total_norm + 1e-6);
+    if rate < 1. {
+        for param in params {
+            if let Some(grad) = param.get_option_grad() {
+                param.set_grad(grad * rate);
+            }
+        }
+    }
+    total_norm
 }
 
 pub struct SGD {
-    lr: f32,
-    target: Model,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
 }
 
 impl SGD {
-    pub fn new(lr: f32, target: Model) -> Self {
-        SGD { lr, target }
+    pub fn new(lr: f32, params: impl IntoParamGroups) -> Self {
+        SGD {
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr,
+                    ..Default::default()
+                },
+                params,
+            ),
+            hooks: Vec::new(),
+        }
     }
 }
 
 impl Optimizer for SGD {
-    fn get_params(&mut self) -> Vec<VBox> {
-        self.target.get_params()
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
     }
-    fn update_one(&mut self, param: VBox) {
-        param.set_array(param.get_array() - self.lr * param.get_grad())
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
     }
 }
 
 pub struct Momentum {
-    lr: f32,
-    momentum: f32,
     vs: HashMap<VBox, Array>,
-    target: Model,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
 }
 
 impl Momentum {
-    pub fn new(lr: f32, momentum: f32, target: Model) -> Self {
-        Momentum {
+    pub fn new(lr: f32, momentum: f32, params: impl IntoParamGroups) -> Self {
+        let defaults = GroupOptions {
             lr,
             momentum,
+            ..Default::default()
+        };
+        Momentum {
             vs: HashMap::new(),
-            target,
+            groups: ParamGroups::new(defaults, params),
+            hooks: Vec::new(),
         }
     }
 }
 
 impl Optimizer for Momentum {
-    fn get_params(&mut self) -> Vec<VBox> {
-        self.target.get_params()
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
     }
-    fn update_one(&mut self, param: VBox) {
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
         let v = self
             .vs
             .entry(param.clone())
             .or_insert_with(|| Array::zeros(&param.get_shape()));
-        *v = &*v * self.momentum;
-        *v = &*v - self.lr * param.get_grad();
+        *v = &*v * options.momentum;
+        *v = &*v - options.lr * grad;
         param.set_array(param.get_array() + &*v)
     }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("vs", &mut self.vs)]
+    }
+}
+
+pub struct Nesterov {
+    vs: HashMap<VBox, Array>,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl Nesterov {
+    pub fn new(lr: f32, momentum: f32, params: impl IntoParamGroups) -> Self {
+        let defaults = GroupOptions {
+            lr,
+            momentum,
+            ..Default::default()
+        };
+        Nesterov {
+            vs: HashMap::new(),
+            groups: ParamGroups::new(defaults, params),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Nesterov {
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
+        let v = self
+            .vs
+            .entry(param.clone())
+            .or_insert_with(|| Array::zeros(&param.get_shape()));
+        *v = &*v * options.momentum - options.lr * &grad;
+        param.set_array(param.get_array() + &*v * options.momentum - options.lr * grad)
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("vs", &mut self.vs)]
+    }
+}
+
+pub struct AdaGrad {
+    eps: f32,
+    hs: HashMap<VBox, Array>,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl AdaGrad {
+    pub fn new(lr: f32, params: impl IntoParamGroups) -> Self {
+        AdaGrad {
+            eps: 1e-8,
+            hs: HashMap::new(),
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr,
+                    ..Default::default()
+                },
+                params,
+            ),
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self {
+        self.eps = eps;
+        self
+    }
+}
+
+impl Optimizer for AdaGrad {
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
+        let h = self
+            .hs
+            .entry(param.clone())
+            .or_insert_with(|| Array::zeros(&param.get_shape()));
+        *h = &*h + grad.powi(2);
+        param.set_array(param.get_array() - options.lr * grad / (h.sqrt() + self.eps))
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("hs", &mut self.hs)]
+    }
+}
+
+pub struct RMSProp {
+    rho: f32,
+    eps: f32,
+    hs: HashMap<VBox, Array>,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl RMSProp {
+    pub fn new(lr: f32, rho: f32, params: impl IntoParamGroups) -> Self {
+        RMSProp {
+            rho,
+            eps: 1e-8,
+            hs: HashMap::new(),
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr,
+                    ..Default::default()
+                },
+                params,
+            ),
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self {
+        self.eps = eps;
+        self
+    }
+}
+
+impl Optimizer for RMSProp {
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
+        let h = self
+            .hs
+            .entry(param.clone())
+            .or_insert_with(|| Array::zeros(&param.get_shape()));
+        *h = &*h * self.rho + (1. - self.rho) * grad.powi(2);
+        param.set_array(param.get_array() - options.lr * grad / (h.sqrt() + self.eps))
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("hs", &mut self.hs)]
+    }
+}
+
+pub struct AdaDelta {
+    rho: f32,
+    eps: f32,
+    msg: HashMap<VBox, Array>,
+    msdx: HashMap<VBox, Array>,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl AdaDelta {
+    pub fn new(rho: f32, params: impl IntoParamGroups) -> Self {
+        AdaDelta {
+            rho,
+            eps: 1e-6,
+            msg: HashMap::new(),
+            msdx: HashMap::new(),
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr: 1.,
+                    ..Default::default()
+                },
+                params,
+            ),
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self {
+        self.eps = eps;
+        self
+    }
+}
+
+impl Optimizer for AdaDelta {
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
+        let zeros = || Array::zeros(&param.get_shape());
+        let msg = self.msg.entry(param.clone()).or_insert_with(zeros);
+        let msdx = self.msdx.entry(param.clone()).or_insert_with(zeros);
+
+        *msg = &*msg * self.rho + (1. - self.rho) * grad.powi(2);
+        let dx = (&*msdx + self.eps).sqrt() / (&*msg + self.eps).sqrt() * grad;
+        *msdx = &*msdx * self.rho + (1. - self.rho) * dx.powi(2);
+        param.set_array(param.get_array() - options.lr * dx)
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("msg", &mut self.msg), ("msdx", &mut self.msdx)]
+    }
+}
+
+pub struct Adam {
+    beta1: f32,
+    beta2: f32,
+    eps: f32,
+    t: i32,
+    ms: HashMap<VBox, Array>,
+    vs: HashMap<VBox, Array>,
+    groups: ParamGroups,
+    hooks: Vec<Box<dyn Hook>>,
+}
+
+impl Adam {
+    pub fn new(lr: f32, params: impl IntoParamGroups) -> Self {
+        Adam {
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+            t: 0,
+            ms: HashMap::new(),
+            vs: HashMap::new(),
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr,
+                    ..Default::default()
+                },
+                params,
+            ),
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn betas(mut self, beta1: f32, beta2: f32) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self {
+        self.eps = eps;
+        self
+    }
+}
+
+impl Optimizer for Adam {
+    fn update(&mut self) {
+        self.t += 1;
+        let params = self.get_params();
+        self.run_hooks(&params);
+        for (param, options) in self.groups.resolve() {
+            self.update_one(param, &options)
+        }
+    }
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        let grad = decayed_grad(&param, options);
+        let zeros = || Array::zeros(&param.get_shape());
+        let m = self.ms.entry(param.clone()).or_insert_with(zeros);
+        let v = self.vs.entry(param.clone()).or_insert_with(zeros);
+
+        *m = &*m * self.beta1 + (1. - self.beta1) * &grad;
+        *v = &*v * self.beta2 + (1. - self.beta2) * grad.powi(2);
+        let m_hat = &*m / (1. - self.beta1.powi(self.t));
+        let v_hat = &*v / (1. - self.beta2.powi(self.t));
+        param.set_array(param.get_array() - options.lr * m_hat / (v_hat.sqrt() + self.eps))
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        vec![("ms", &mut self.ms), ("vs", &mut self.vs)]
+    }
+    fn state_dict(&mut self) -> StateDict {
+        let mut state_dict = save_state(self);
+        state_dict.insert("t".to_string(), scalar(self.t as f32));
+        state_dict
+    }
+    fn load_state_dict(&mut self, state_dict: &StateDict) -> Result<()> {
+        let t = get_scalar(state_dict, "t")?;
+        load_state(self, state_dict, &["t"])?;
+        self.t = t as i32;
+        Ok(())
+    }
+}
+
+// Adam with decoupled weight decay: `weight_decay` shrinks the params directly
+// instead of being added to the grads.
+pub struct AdamW {
+    adam: Adam,
+}
+
+impl AdamW {
+    pub fn new(lr: f32, weight_decay: f32, params: impl IntoParamGroups) -> Self {
+        let mut adam = Adam::new(lr, params);
+        adam.groups.defaults.weight_decay = weight_decay;
+        AdamW { adam }
+    }
+
+    pub fn betas(mut self, beta1: f32, beta2: f32) -> Self {
+        self.adam = self.adam.betas(beta1, beta2);
+        self
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self {
+        self.adam = self.adam.eps(eps);
+        self
+    }
+}
+
+impl Optimizer for AdamW {
+    fn update(&mut self) {
+        self.adam.t += 1;
+        let params = self.get_params();
+        self.run_hooks(&params);
+        for (param, options) in self.adam.groups.resolve() {
+            self.update_one(param, &options)
+        }
+    }
+    fn param_groups(&self) -> &ParamGroups {
+        self.adam.param_groups()
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        self.adam.param_groups_mut()
+    }
+    fn update_one(&mut self, param: VBox, options: &GroupOptions) {
+        param.set_array(param.get_array() * (1. - options.lr * options.weight_decay));
+        let options = GroupOptions {
+            weight_decay: 0.,
+            ..*options
+        };
+        self.adam.update_one(param, &options)
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        self.adam.hooks()
+    }
+    fn slots(&mut self) -> Vec<(&'static str, &mut HashMap<VBox, Array>)> {
+        self.adam.slots()
+    }
+    fn state_dict(&mut self) -> StateDict {
+        self.adam.state_dict()
+    }
+    fn load_state_dict(&mut self, state_dict: &StateDict) -> Result<()> {
+        self.adam.load_state_dict(state_dict)
+    }
 }
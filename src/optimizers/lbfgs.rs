@@ -0,0 +1,519 @@
+use std::collections::VecDeque;
+
+use super::{get_scalar, scalar, GroupOptions, Hook, IntoParamGroups, Optimizer, ParamGroups};
+use crate::{
+    array::Array,
+    error::{DezeroError, Result},
+    state_dict::StateDict,
+    variable::VBox,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineSearch {
+    Fixed,
+    StrongWolfe,
+}
+
+// Full-batch quasi-Newton optimizer. All params are treated as one flat vector,
+// so per-group hyperparameters are ignored. The curvature history carries over
+// between calls to `step`. Steps are chosen by a strong-Wolfe line search
+// unless `line_search(LineSearch::Fixed)` is set.
+pub struct LBFGS {
+    groups: ParamGroups,
+    max_iter: usize,
+    // Defaults to `max_iter * 5 / 4`.
+    max_eval: Option<usize>,
+    tolerance_grad: f32,
+    tolerance_change: f32,
+    history_size: usize,
+    line_search: LineSearch,
+    hooks: Vec<Box<dyn Hook>>,
+    state: State,
+}
+
+#[derive(Default)]
+struct State {
+    d: Vec<f32>,
+    t: f32,
+    // Curvature pairs (s, y, 1 / y·s), oldest first.
+    history: VecDeque<(Vec<f32>, Vec<f32>, f32)>,
+    h_diag: f32,
+    prev_grad: Vec<f32>,
+    prev_loss: f32,
+    n_iter: usize,
+}
+
+impl LBFGS {
+    pub fn new(lr: f32, params: impl IntoParamGroups) -> Self {
+        LBFGS {
+            groups: ParamGroups::new(
+                GroupOptions {
+                    lr,
+                    ..Default::default()
+                },
+                params,
+            ),
+            max_iter: 20,
+            max_eval: None,
+            tolerance_grad: 1e-7,
+            tolerance_change: 1e-9,
+            history_size: 100,
+            line_search: LineSearch::StrongWolfe,
+            hooks: Vec::new(),
+            state: State::default(),
+        }
+    }
+
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    pub fn max_eval(mut self, max_eval: usize) -> Self {
+        self.max_eval = Some(max_eval);
+        self
+    }
+
+    pub fn tolerance_grad(mut self, tolerance_grad: f32) -> Self {
+        self.tolerance_grad = tolerance_grad;
+        self
+    }
+
+    pub fn tolerance_change(mut self, tolerance_change: f32) -> Self {
+        self.tolerance_change = tolerance_change;
+        self
+    }
+
+    pub fn history_size(mut self, history_size: usize) -> Self {
+        self.history_size = history_size;
+        self
+    }
+
+    pub fn line_search(mut self, line_search: LineSearch) -> Self {
+        self.line_search = line_search;
+        self
+    }
+
+    // `closure` recomputes the loss from the current params; it is called up to
+    // `max_eval` times, with grads cleared, backpropagated and passed through the
+    // hooks here. Returns the loss at the start of the step.
+    pub fn step(&mut self, mut closure: impl FnMut() -> VBox) -> f32 {
+        let params = self.get_params();
+        let mut hooks = std::mem::take(&mut self.hooks);
+        let mut evaluate = || {
+            for param in &params {
+                param.clear_grad();
+            }
+            let loss = closure();
+            loss.backward();
+            for hook in hooks.iter_mut() {
+                hook.apply(&params);
+            }
+            (loss.get_array().to_vec()[0], flat_grad(&params))
+        };
+
+        let (loss, grad) = evaluate();
+        if max_abs(&grad) > self.tolerance_grad {
+            self.iterate(&params, loss, grad, Some(&mut evaluate));
+        }
+        self.hooks = hooks;
+        loss
+    }
+
+    // Runs up to `max_iter` iterations starting from `loss` and `grad` at the
+    // current params. Without `evaluate`, a single fixed step is taken.
+    fn iterate(
+        &mut self,
+        params: &[VBox],
+        mut loss: f32,
+        mut grad: Vec<f32>,
+        mut evaluate: Option<&mut dyn FnMut() -> (f32, Vec<f32>)>,
+    ) {
+        let lr = self.lr();
+        let max_iter = match evaluate {
+            Some(_) => self.max_iter,
+            None => 1,
+        };
+        let max_eval = self.max_eval.unwrap_or(self.max_iter * 5 / 4);
+        let mut num_evals = 1;
+        let state = &mut self.state;
+
+        for n_iter in 1..=max_iter {
+            state.n_iter += 1;
+            if state.n_iter == 1 {
+                state.d = grad.iter().map(|g| -g).collect();
+                state.history.clear();
+                state.h_diag = 1.;
+            } else {
+                let y = axpy(&grad, -1., &state.prev_grad);
+                let s = state.d.iter().map(|d| d * state.t).collect::<Vec<_>>();
+                let ys = dot(&y, &s);
+                if ys > 1e-10 {
+                    if state.history.len() == self.history_size {
+                        state.history.pop_front();
+                    }
+                    state.h_diag = ys / dot(&y, &y);
+                    state.history.push_back((s, y, ys.recip()));
+                }
+                state.d = two_loop(&state.history, &grad, state.h_diag);
+            }
+            state.prev_grad = grad.clone();
+            state.prev_loss = loss;
+
+            state.t = if state.n_iter == 1 {
+                lr * grad.iter().map(|g| g.abs()).sum::<f32>().recip().min(1.)
+            } else {
+                lr
+            };
+            let gtd = dot(&grad, &state.d);
+            if gtd > -self.tolerance_change {
+                break;
+            }
+
+            let x = flat_params(params);
+            match (self.line_search, evaluate.as_mut()) {
+                (LineSearch::StrongWolfe, Some(evaluate)) => {
+                    let d = &state.d;
+                    let mut directional = |t: f32| {
+                        set_params(params, &axpy(&x, t, d));
+                        let result = evaluate();
+                        set_params(params, &x);
+                        result
+                    };
+                    let (point, evals) = strong_wolfe(
+                        &mut directional,
+                        state.t,
+                        d,
+                        (loss, &grad, gtd),
+                        self.tolerance_change,
+                    );
+                    loss = point.f;
+                    grad = point.g;
+                    state.t = point.t;
+                    num_evals += evals;
+                    set_params(params, &axpy(&x, state.t, d));
+                }
+                (_, evaluate) => {
+                    set_params(params, &axpy(&x, state.t, &state.d));
+                    if let Some(evaluate) = evaluate.filter(|_| n_iter != max_iter) {
+                        (loss, grad) = evaluate();
+                        num_evals += 1;
+                    }
+                }
+            }
+
+            if n_iter == max_iter
+                || num_evals >= max_eval
+                || max_abs(&grad) <= self.tolerance_grad
+                || max_abs(&state.d) * state.t.abs() <= self.tolerance_change
+                || (loss - state.prev_loss).abs() < self.tolerance_change
+            {
+                break;
+            }
+        }
+    }
+}
+
+// `update` takes a single fixed step from the grads left by the last `backward`;
+// more iterations and the line search need `step` with a closure. `update_one`
+// keeps the default gradient step, since the curvature pairs span all params.
+impl Optimizer for LBFGS {
+    fn update(&mut self) {
+        let params = self.get_params();
+        self.run_hooks(&params);
+        let grad = flat_grad(&params);
+        if max_abs(&grad) > self.tolerance_grad {
+            self.iterate(&params, f32::NAN, grad, None);
+        }
+    }
+    fn param_groups(&self) -> &ParamGroups {
+        &self.groups
+    }
+    fn param_groups_mut(&mut self) -> &mut ParamGroups {
+        &mut self.groups
+    }
+    fn hooks(&mut self) -> &mut Vec<Box<dyn Hook>> {
+        &mut self.hooks
+    }
+    // Saves the curvature history as `history.<i>.{s,y,rho}`, oldest first,
+    // along with the last direction, step length and grad.
+    fn state_dict(&mut self) -> StateDict {
+        let state = &self.state;
+        let mut state_dict = StateDict::new();
+        for (key, x) in [
+            ("lr", self.lr()),
+            ("t", state.t),
+            ("h_diag", state.h_diag),
+            ("prev_loss", state.prev_loss),
+            ("n_iter", state.n_iter as f32),
+        ] {
+            state_dict.insert(key.to_string(), scalar(x));
+        }
+        state_dict.insert("d".to_string(), vector(&state.d));
+        state_dict.insert("prev_grad".to_string(), vector(&state.prev_grad));
+        for (i, (s, y, rho)) in state.history.iter().enumerate() {
+            state_dict.insert(format!("history.{}.s", i), vector(s));
+            state_dict.insert(format!("history.{}.y", i), vector(y));
+            state_dict.insert(format!("history.{}.rho", i), scalar(*rho));
+        }
+        state_dict
+    }
+    fn load_state_dict(&mut self, state_dict: &StateDict) -> Result<()> {
+        let lr = get_scalar(state_dict, "lr")?;
+        let n_iter = get_scalar(state_dict, "n_iter")? as usize;
+        let size = match n_iter {
+            0 => 0,
+            _ => self.get_params().iter().map(|p| p.get_array().size()).sum(),
+        };
+        let mut state = State {
+            d: get_vector(state_dict, "d", size)?,
+            t: get_scalar(state_dict, "t")?,
+            history: VecDeque::new(),
+            h_diag: get_scalar(state_dict, "h_diag")?,
+            prev_grad: get_vector(state_dict, "prev_grad", size)?,
+            prev_loss: get_scalar(state_dict, "prev_loss")?,
+            n_iter,
+        };
+        while state_dict.contains_key(&format!("history.{}.rho", state.history.len())) {
+            let i = state.history.len();
+            state.history.push_back((
+                get_vector(state_dict, &format!("history.{}.s", i), size)?,
+                get_vector(state_dict, &format!("history.{}.y", i), size)?,
+                get_scalar(state_dict, &format!("history.{}.rho", i))?,
+            ));
+        }
+        let known = 7 + 3 * state.history.len();
+        if state_dict.len() > known {
+            let key = state_dict
+                .keys()
+                .find(|key| !is_state_key(key, state.history.len()))
+                .unwrap();
+            return Err(DezeroError::UnexpectedKey(key.clone()));
+        }
+        while state.history.len() > self.history_size {
+            state.history.pop_front();
+        }
+        self.state = state;
+        self.set_lr(lr);
+        Ok(())
+    }
+}
+
+fn vector(x: &[f32]) -> Array {
+    Array::new(x.to_vec(), vec![x.len()])
+}
+
+fn get_vector(state_dict: &StateDict, key: &str, len: usize) -> Result<Vec<f32>> {
+    let array = state_dict
+        .get(key)
+        .ok_or_else(|| DezeroError::MissingKey(key.to_string()))?;
+    if *array.get_shape() != [len] {
+        return Err(DezeroError::ParamShape {
+            key: key.to_string(),
+            expected: vec![len],
+            found: array.get_shape().clone(),
+        });
+    }
+    Ok(array.to_vec())
+}
+
+fn is_state_key(key: &str, history_len: usize) -> bool {
+    let names = ["lr", "t", "h_diag", "prev_loss", "n_iter", "d", "prev_grad"];
+    names.contains(&key)
+        || key
+            .strip_prefix("history.")
+            .and_then(|rest| rest.split_once('.'))
+            .and_then(|(i, name)| Some((i.parse::<usize>().ok()?, name)))
+            .is_some_and(|(i, name)| i < history_len && ["s", "y", "rho"].contains(&name))
+}
+
+fn flat_grad(params: &[VBox]) -> Vec<f32> {
+    params
+        .iter()
+        .flat_map(|param| match param.get_option_grad() {
+            Some(grad) => grad.to_vec(),
+            None => vec![0.; param.get_array().size()],
+        })
+        .collect()
+}
+
+fn flat_params(params: &[VBox]) -> Vec<f32> {
+    params
+        .iter()
+        .flat_map(|param| param.get_array().to_vec())
+        .collect()
+}
+
+fn set_params(params: &[VBox], x: &[f32]) {
+    let mut offset = 0;
+    for param in params {
+        let shape = param.get_shape();
+        let size = shape.iter().product::<usize>();
+        param.set_array(Array::new(x[offset..offset + size].to_vec(), shape));
+        offset += size;
+    }
+}
+
+fn two_loop(history: &VecDeque<(Vec<f32>, Vec<f32>, f32)>, grad: &[f32], h_diag: f32) -> Vec<f32> {
+    let mut q = grad.iter().map(|g| -g).collect::<Vec<_>>();
+    let mut alphas = vec![0.; history.len()];
+    for (i, (s, y, rho)) in history.iter().enumerate().rev() {
+        alphas[i] = rho * dot(s, &q);
+        q = axpy(&q, -alphas[i], y);
+    }
+    let mut r = q.iter().map(|q| q * h_diag).collect::<Vec<_>>();
+    for ((s, y, rho), alpha) in history.iter().zip(alphas) {
+        let beta = rho * dot(y, &r);
+        r = axpy(&r, alpha - beta, s);
+    }
+    r
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn axpy(x: &[f32], t: f32, d: &[f32]) -> Vec<f32> {
+    x.iter().zip(d).map(|(x, d)| x + t * d).collect()
+}
+
+fn max_abs(x: &[f32]) -> f32 {
+    x.iter().fold(0., |m, x| m.max(x.abs()))
+}
+
+#[derive(Clone)]
+struct Point {
+    t: f32,
+    f: f32,
+    g: Vec<f32>,
+    gtd: f32,
+}
+
+// Minimizer of the cubic interpolating two points, clamped to `bounds`
+// (the interval between them by default).
+fn cubic_interpolate(p1: &Point, p2: &Point, bounds: Option<(f32, f32)>) -> f32 {
+    let (min_bound, max_bound) = bounds.unwrap_or((p1.t.min(p2.t), p1.t.max(p2.t)));
+    let d1 = p1.gtd + p2.gtd - 3. * (p1.f - p2.f) / (p1.t - p2.t);
+    let d2_square = d1 * d1 - p1.gtd * p2.gtd;
+    if d2_square < 0. {
+        return (min_bound + max_bound) / 2.;
+    }
+    let d2 = d2_square.sqrt();
+    let min_pos = if p1.t <= p2.t {
+        p2.t - (p2.t - p1.t) * ((p2.gtd + d2 - d1) / (p2.gtd - p1.gtd + 2. * d2))
+    } else {
+        p1.t - (p1.t - p2.t) * ((p1.gtd + d2 - d1) / (p1.gtd - p2.gtd + 2. * d2))
+    };
+    min_pos.max(min_bound).min(max_bound)
+}
+
+// Finds a step length `t` along `d` satisfying the strong Wolfe conditions,
+// bracketing an interval first and then zooming into it with cubic
+// interpolation (Nocedal & Wright, algorithms 3.5 and 3.6).
+fn strong_wolfe(
+    directional: &mut dyn FnMut(f32) -> (f32, Vec<f32>),
+    t: f32,
+    d: &[f32],
+    (f, g, gtd): (f32, &[f32], f32),
+    tolerance_change: f32,
+) -> (Point, usize) {
+    const C1: f32 = 1e-4;
+    const C2: f32 = 0.9;
+    const MAX_LS: usize = 25;
+
+    let mut evaluate = |t: f32| {
+        let (f, g) = directional(t);
+        Point {
+            t,
+            f,
+            gtd: dot(&g, d),
+            g,
+        }
+    };
+    let start = Point {
+        t: 0.,
+        f,
+        g: g.to_vec(),
+        gtd,
+    };
+    let sufficient_decrease = |p: &Point| p.f <= f + C1 * p.t * gtd;
+    let curvature = |p: &Point| p.gtd.abs() <= -C2 * gtd;
+
+    let mut prev = start.clone();
+    let mut new = evaluate(t);
+    let mut num_evals = 1;
+    let mut ls_iter = 0;
+    let mut done = false;
+    let mut bracket = loop {
+        if ls_iter == MAX_LS {
+            break vec![start, new];
+        }
+        if !sufficient_decrease(&new) || (ls_iter > 1 && new.f >= prev.f) {
+            break vec![prev, new];
+        }
+        if curvature(&new) {
+            done = true;
+            break vec![new];
+        }
+        if new.gtd >= 0. {
+            break vec![prev, new];
+        }
+        let min_step = new.t + 0.01 * (new.t - prev.t);
+        let max_step = new.t * 10.;
+        let t = cubic_interpolate(&prev, &new, Some((min_step, max_step)));
+        prev = new;
+        new = evaluate(t);
+        num_evals += 1;
+        ls_iter += 1;
+    };
+
+    let order = |bracket: &[Point]| match bracket {
+        [a, b] if a.f > b.f => (1, 0),
+        _ => (0, 1),
+    };
+    let (mut low, mut high) = order(&bracket);
+    let mut insufficient_progress = false;
+    while !done && ls_iter < MAX_LS {
+        let (lo, hi) = (
+            bracket[0].t.min(bracket[1].t),
+            bracket[0].t.max(bracket[1].t),
+        );
+        if (hi - lo) * max_abs(d) < tolerance_change {
+            break;
+        }
+        // Keep trial points away from the ends of the bracket so that it
+        // keeps shrinking.
+        let mut t = cubic_interpolate(&bracket[0], &bracket[1], None);
+        let eps = 0.1 * (hi - lo);
+        if (hi - t).min(t - lo) < eps {
+            if insufficient_progress || t >= hi || t <= lo {
+                t = if (t - hi).abs() < (t - lo).abs() {
+                    hi - eps
+                } else {
+                    lo + eps
+                };
+                insufficient_progress = false;
+            } else {
+                insufficient_progress = true;
+            }
+        } else {
+            insufficient_progress = false;
+        }
+
+        let point = evaluate(t);
+        num_evals += 1;
+        ls_iter += 1;
+        if !sufficient_decrease(&point) || point.f >= bracket[low].f {
+            bracket[high] = point;
+            (low, high) = order(&bracket);
+        } else {
+            if curvature(&point) {
+                done = true;
+            } else if point.gtd * (bracket[high].t - bracket[low].t) >= 0. {
+                bracket[high] = bracket[low].clone();
+            }
+            bracket[low] = point;
+        }
+    }
+    (bracket.swap_remove(low), num_evals)
+}
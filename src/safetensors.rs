@@ -0,0 +1,379 @@
+use crate::{
+    array::Array,
+    error::{DezeroError, Result},
+    state_dict::StateDict,
+};
+use std::{cmp::Ordering, fs, path::Path};
+
+pub fn save(path: impl AsRef<Path>, state_dict: &StateDict) -> Result<()> {
+    fs::write(path, serialize(state_dict))?;
+    Ok(())
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<StateDict> {
+    deserialize(&fs::read(path)?)
+}
+
+pub fn serialize(state_dict: &StateDict) -> Vec<u8> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    for (name, array) in state_dict {
+        let end = offset + array.get_shape().iter().product::<usize>() * 4;
+        entries.push(format!(
+            "{}:{{\"dtype\":\"F32\",\"shape\":{:?},\"data_offsets\":[{},{}]}}",
+            quote(name),
+            array.get_shape(),
+            offset,
+            end
+        ));
+        offset = end;
+    }
+    let mut header = format!("{{{}}}", entries.join(","));
+    header += &" ".repeat((8 - header.len() % 8) % 8);
+
+    let mut buf = Vec::with_capacity(8 + header.len() + offset);
+    buf.extend_from_slice(&(header.len() as u64).to_le_bytes());
+    buf.extend_from_slice(header.as_bytes());
+    for array in state_dict.values() {
+        for x in array.iter() {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+    buf
+}
+
+pub fn deserialize(bytes: &[u8]) -> Result<StateDict> {
+    let header_len = bytes
+        .get(..8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+        .filter(|&len| len <= bytes.len() - 8)
+        .ok_or_else(|| format_error("missing safetensors header"))?;
+    let header = bytes
+        .get(8..8 + header_len)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| format_error("invalid safetensors header"))?;
+    let buffer = &bytes[8 + header_len..];
+
+    let entries = match Json::parse(header)? {
+        Json::Object(entries) => entries,
+        _ => return Err(format_error("safetensors header must be an object")),
+    };
+
+    let mut state_dict = StateDict::new();
+    for (name, info) in entries {
+        if name == "__metadata__" {
+            continue;
+        }
+        let dtype = info.get("dtype").and_then(Json::as_str);
+        let shape = info.get("shape").and_then(Json::as_usizes);
+        let offsets = info.get("data_offsets").and_then(Json::as_usizes);
+        let (dtype, shape, (begin, end)) = match (dtype, shape, offsets.as_deref()) {
+            (Some(dtype), Some(shape), Some(&[begin, end])) => (dtype, shape, (begin, end)),
+            _ => return Err(format_error(&format!("invalid entry for {}", name))),
+        };
+
+        let data = buffer
+            .get(begin..end)
+            .ok_or_else(|| format_error(&format!("data of {} is out of bounds", name)))?;
+        let data = match dtype {
+            "F32" => decode(data, 4, |b| f32::from_le_bytes(b.try_into().unwrap())),
+            "F64" => decode(data, 8, |b| {
+                f64::from_le_bytes(b.try_into().unwrap()) as f32
+            }),
+            "F16" => decode(data, 2, |b| f16_to_f32(u16::from_le_bytes([b[0], b[1]]))),
+            "BF16" => decode(data, 2, |b| {
+                f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16)
+            }),
+            dtype => return Err(format_error(&format!("unsupported dtype {}", dtype))),
+        };
+        state_dict.insert(name, Array::try_new(data, shape)?);
+    }
+    Ok(state_dict)
+}
+
+// Maps PyTorch `nn.Linear` parameters (`<prefix>.weight` of shape [out, in] and
+// `<prefix>.bias`) to the `layers.<i>.w` / `layers.<i>.b` names of `layers::MLP`.
+pub fn from_torch(state_dict: StateDict) -> StateDict {
+    let mut prefixes = state_dict
+        .keys()
+        .filter_map(|key| key.rsplit_once('.').map(|(prefix, _)| prefix.to_string()))
+        .collect::<Vec<_>>();
+    prefixes.sort_by(|a, b| natural_cmp(a, b));
+    prefixes.dedup();
+
+    state_dict
+        .into_iter()
+        .map(|(key, array)| {
+            let (prefix, name) = match key.rsplit_once('.') {
+                Some(split) => split,
+                None => return (key, array),
+            };
+            let i = prefixes.iter().position(|p| p == prefix).unwrap();
+            match name {
+                "weight" => (format!("layers.{}.w", i), array.transpose()),
+                "bias" => (format!("layers.{}.b", i), array),
+                _ => (key, array),
+            }
+        })
+        .collect()
+}
+
+pub fn is_torch(state_dict: &StateDict) -> bool {
+    !state_dict.is_empty()
+        && state_dict
+            .keys()
+            .all(|key| key.ends_with(".weight") || key.ends_with(".bias"))
+}
+
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let parts = |s: &str| {
+        s.split('.')
+            .map(|p| (p.parse::<u64>().ok(), p.to_string()))
+            .collect::<Vec<_>>()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+fn decode(data: &[u8], size: usize, f: impl Fn(&[u8]) -> f32) -> Vec<f32> {
+    data.chunks_exact(size).map(f).collect()
+}
+
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = ((h >> 15) as u32) << 31;
+    let exp = ((h >> 10) & 0x1f) as u32;
+    let frac = (h & 0x3ff) as u32;
+    let bits = match exp {
+        0 if frac == 0 => sign,
+        0 => {
+            let value = frac as f32 * (2f32).powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        0x1f => sign | 0x7f80_0000 | (frac << 13),
+        _ => sign | ((exp + 112) << 23) | (frac << 13),
+    };
+    f32::from_bits(bits)
+}
+
+fn format_error(message: &str) -> DezeroError {
+    DezeroError::Format(message.to_string())
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            c if (c as u32) < 0x20 => quoted += &format!("\\u{:04x}", c as u32),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+enum Json {
+    Object(Vec<(String, Json)>),
+    Array(Vec<Json>),
+    String(String),
+    Number(f64),
+    Literal,
+}
+
+impl Json {
+    fn parse(s: &str) -> Result<Json> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_ws();
+        if parser.pos != parser.chars.len() {
+            return Err(format_error("trailing characters in safetensors header"));
+        }
+        Ok(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_usizes(&self) -> Option<Vec<usize>> {
+        match self {
+            Json::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Json::Number(n) if *n >= 0. && n.fract() == 0. => Some(*n as usize),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self) -> DezeroError {
+        format_error(&format!(
+            "unexpected character at {} in safetensors header",
+            self.pos
+        ))
+    }
+
+    fn skip_ws(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_ws();
+        if self.chars.get(self.pos) != Some(&c) {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json> {
+        self.skip_ws();
+        match self.chars.get(self.pos) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => {
+                for literal in ["true", "false", "null"] {
+                    let end = self.pos + literal.len();
+                    if self
+                        .chars
+                        .get(self.pos..end)
+                        .is_some_and(|s| s.iter().copied().eq(literal.chars()))
+                    {
+                        self.pos = end;
+                        return Ok(Json::Literal);
+                    }
+                }
+                Err(self.error())
+            }
+            None => Err(self.error()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_ws();
+        if self.chars.get(self.pos) == Some(&'}') {
+            self.pos += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_ws();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.chars.get(self.pos) == Some(&']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = *self.chars.get(self.pos).ok_or_else(|| self.error())?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = *self.chars.get(self.pos).ok_or_else(|| self.error())?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let hex = self
+                                .chars
+                                .get(self.pos..self.pos + 4)
+                                .map(|h| h.iter().collect::<String>())
+                                .ok_or_else(|| self.error())?;
+                            let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error())?;
+                            s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                            self.pos += 4;
+                        }
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| self.error())
+    }
+}
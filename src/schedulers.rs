@@ -0,0 +1,243 @@
+use std::f32::consts::PI;
+
+use crate::optimizers::Optimizer;
+
+pub trait LrScheduler {
+    fn next_lr(&mut self, metric: Option<f32>) -> f32;
+
+    fn step(&mut self, optimizer: &mut dyn Optimizer) {
+        let lr = self.next_lr(None);
+        optimizer.set_lr(lr);
+    }
+
+    fn step_with_metric(&mut self, optimizer: &mut dyn Optimizer, metric: f32) {
+        let lr = self.next_lr(Some(metric));
+        optimizer.set_lr(lr);
+    }
+}
+
+pub struct StepLR {
+    base_lr: f32,
+    step_size: usize,
+    gamma: f32,
+    t: usize,
+}
+
+impl StepLR {
+    pub fn new(optimizer: &mut dyn Optimizer, step_size: usize, gamma: f32) -> Self {
+        assert!(step_size > 0, "StepLR step_size must be positive");
+        StepLR {
+            base_lr: optimizer.lr(),
+            step_size,
+            gamma,
+            t: 0,
+        }
+    }
+}
+
+impl LrScheduler for StepLR {
+    fn next_lr(&mut self, _metric: Option<f32>) -> f32 {
+        self.t += 1;
+        self.base_lr * self.gamma.powi((self.t / self.step_size) as i32)
+    }
+}
+
+pub struct ExponentialLR {
+    base_lr: f32,
+    gamma: f32,
+    t: usize,
+}
+
+impl ExponentialLR {
+    pub fn new(optimizer: &mut dyn Optimizer, gamma: f32) -> Self {
+        ExponentialLR {
+            base_lr: optimizer.lr(),
+            gamma,
+            t: 0,
+        }
+    }
+}
+
+impl LrScheduler for ExponentialLR {
+    fn next_lr(&mut self, _metric: Option<f32>) -> f32 {
+        self.t += 1;
+        self.base_lr * self.gamma.powi(self.t as i32)
+    }
+}
+
+pub struct CosineAnnealingWarmRestarts {
+    base_lr: f32,
+    eta_min: f32,
+    t_i: usize,
+    t_mult: usize,
+    t_cur: usize,
+}
+
+impl CosineAnnealingWarmRestarts {
+    pub fn new(optimizer: &mut dyn Optimizer, t_0: usize, t_mult: usize, eta_min: f32) -> Self {
+        assert!(t_0 > 0, "CosineAnnealingWarmRestarts t_0 must be positive");
+        assert!(
+            t_mult > 0,
+            "CosineAnnealingWarmRestarts t_mult must be at least 1"
+        );
+        CosineAnnealingWarmRestarts {
+            base_lr: optimizer.lr(),
+            eta_min,
+            t_i: t_0,
+            t_mult,
+            t_cur: 0,
+        }
+    }
+}
+
+impl LrScheduler for CosineAnnealingWarmRestarts {
+    fn next_lr(&mut self, _metric: Option<f32>) -> f32 {
+        self.t_cur += 1;
+        if self.t_cur >= self.t_i {
+            self.t_cur -= self.t_i;
+            self.t_i *= self.t_mult;
+        }
+        let progress = self.t_cur as f32 / self.t_i as f32;
+        self.eta_min + (self.base_lr - self.eta_min) * (1. + (PI * progress).cos()) / 2.
+    }
+}
+
+pub struct LinearWarmup {
+    base_lr: f32,
+    start_factor: f32,
+    warmup_steps: usize,
+    t: usize,
+}
+
+impl LinearWarmup {
+    pub fn new(optimizer: &mut dyn Optimizer, start_factor: f32, warmup_steps: usize) -> Self {
+        assert!(
+            warmup_steps > 0,
+            "LinearWarmup warmup_steps must be positive"
+        );
+        let base_lr = optimizer.lr();
+        optimizer.set_lr(base_lr * start_factor);
+        LinearWarmup {
+            base_lr,
+            start_factor,
+            warmup_steps,
+            t: 0,
+        }
+    }
+}
+
+impl LrScheduler for LinearWarmup {
+    fn next_lr(&mut self, _metric: Option<f32>) -> f32 {
+        self.t = (self.t + 1).min(self.warmup_steps);
+        let progress = self.t as f32 / self.warmup_steps as f32;
+        self.base_lr * (self.start_factor + (1. - self.start_factor) * progress)
+    }
+}
+
+pub struct ReduceLROnPlateau {
+    lr: f32,
+    factor: f32,
+    patience: usize,
+    threshold: f32,
+    min_lr: f32,
+    best: f32,
+    num_bad_steps: usize,
+}
+
+impl ReduceLROnPlateau {
+    pub fn new(optimizer: &mut dyn Optimizer, factor: f32, patience: usize) -> Self {
+        ReduceLROnPlateau {
+            lr: optimizer.lr(),
+            factor,
+            patience,
+            threshold: 1e-4,
+            min_lr: 0.,
+            best: f32::INFINITY,
+            num_bad_steps: 0,
+        }
+    }
+
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn min_lr(mut self, min_lr: f32) -> Self {
+        self.min_lr = min_lr;
+        self
+    }
+}
+
+impl LrScheduler for ReduceLROnPlateau {
+    // The metric is treated as a loss: the learning rate is reduced once it has
+    // not improved by a relative `threshold` for more than `patience` steps.
+    fn next_lr(&mut self, metric: Option<f32>) -> f32 {
+        let metric = match metric {
+            Some(metric) => metric,
+            None => return self.lr,
+        };
+        if metric < self.best * (1. - self.threshold) {
+            self.best = metric;
+            self.num_bad_steps = 0;
+        } else {
+            self.num_bad_steps += 1;
+        }
+        if self.num_bad_steps > self.patience {
+            self.lr = (self.lr * self.factor).max(self.min_lr);
+            self.num_bad_steps = 0;
+        }
+        self.lr
+    }
+}
+
+pub struct OneCycle {
+    max_lr: f32,
+    initial_lr: f32,
+    min_lr: f32,
+    warmup_steps: usize,
+    total_steps: usize,
+    t: usize,
+}
+
+impl OneCycle {
+    pub fn new(optimizer: &mut dyn Optimizer, max_lr: f32, total_steps: usize) -> Self {
+        OneCycle::with_options(optimizer, max_lr, total_steps, 0.3, 25., 1e4)
+    }
+
+    pub fn with_options(
+        optimizer: &mut dyn Optimizer,
+        max_lr: f32,
+        total_steps: usize,
+        pct_start: f32,
+        div_factor: f32,
+        final_div_factor: f32,
+    ) -> Self {
+        let initial_lr = max_lr / div_factor;
+        optimizer.set_lr(initial_lr);
+        OneCycle {
+            max_lr,
+            initial_lr,
+            min_lr: initial_lr / final_div_factor,
+            warmup_steps: ((pct_start * total_steps as f32) as usize).max(1),
+            total_steps,
+            t: 0,
+        }
+    }
+}
+
+impl LrScheduler for OneCycle {
+    fn next_lr(&mut self, _metric: Option<f32>) -> f32 {
+        self.t = (self.t + 1).min(self.total_steps);
+        let anneal = |start: f32, end: f32, progress: f32| {
+            end + (start - end) * (1. + (PI * progress).cos()) / 2.
+        };
+        if self.t <= self.warmup_steps {
+            let progress = self.t as f32 / self.warmup_steps as f32;
+            anneal(self.initial_lr, self.max_lr, progress)
+        } else {
+            let progress = (self.t - self.warmup_steps) as f32
+                / (self.total_steps - self.warmup_steps).max(1) as f32;
+            anneal(self.max_lr, self.min_lr, progress)
+        }
+    }
+}
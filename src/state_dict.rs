@@ -0,0 +1,95 @@
+use crate::{
+    array::Array,
+    error::{DezeroError, Result},
+};
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub type StateDict = BTreeMap<String, Array>;
+
+const MAGIC: &[u8; 4] = b"DZRW";
+const VERSION: u32 = 1;
+
+// Layout (little-endian): magic, version: u32, count: u32, then per entry
+// name_len: u32, name, ndim: u32, dims: u64 * ndim, data: f32 * size.
+pub fn save(path: impl AsRef<Path>, state_dict: &StateDict) -> Result<()> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&(state_dict.len() as u32).to_le_bytes());
+
+    for (name, array) in state_dict {
+        buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+        buf.extend_from_slice(&(array.get_shape().len() as u32).to_le_bytes());
+        for &dim in array.get_shape() {
+            buf.extend_from_slice(&(dim as u64).to_le_bytes());
+        }
+        for x in array.iter() {
+            buf.extend_from_slice(&x.to_le_bytes());
+        }
+    }
+
+    fs::write(path, buf)?;
+    Ok(())
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<StateDict> {
+    let buf = fs::read(path)?;
+    let mut reader = Reader { buf: &buf, pos: 0 };
+
+    if reader.take(4)? != MAGIC {
+        return Err(DezeroError::Format("not a weights file".to_string()));
+    }
+    let version = reader.read_u32()?;
+    if version != VERSION {
+        return Err(DezeroError::Format(format!(
+            "unsupported weights version {}",
+            version
+        )));
+    }
+
+    let mut state_dict = StateDict::new();
+    for _ in 0..reader.read_u32()? {
+        let len = reader.read_u32()? as usize;
+        let name = String::from_utf8(reader.take(len)?.to_vec())
+            .map_err(|_| DezeroError::Format("parameter name is not utf-8".to_string()))?;
+        let ndim = reader.read_u32()? as usize;
+        let shape = (0..ndim)
+            .map(|_| reader.read_u64().map(|dim| dim as usize))
+            .collect::<Result<Vec<_>>>()?;
+        let size = shape.iter().product::<usize>();
+        let data = reader
+            .take(size * 4)?
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        state_dict.insert(name, Array::try_new(data, shape)?);
+    }
+    Ok(state_dict)
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.buf.len())
+            .ok_or_else(|| DezeroError::Format("unexpected end of file".to_string()))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
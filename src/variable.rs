@@ -9,8 +9,10 @@ pub use vbox::VBox;
 pub use weak_vbox::WeakVBox;
 
 pub struct Variable {
+    name: Option<String>,
     array: Array,
-    grad: Option<Array>,
+    grad: Option<VBox>,
+    tangent: Option<Array>,
     creator: Option<FuncBox>,
     generation: u32,
 }
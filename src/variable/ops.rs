@@ -1,5 +1,6 @@
 use super::VBox;
 use crate::{
+    array::Index,
     functions::{self as F},
     scaler,
 };
@@ -8,42 +9,93 @@ use std::ops::{Add, Div, Mul, Neg, Sub};
 impl VBox {
     pub fn powi(&self, n: i32) -> VBox {
         let func = F::Powi::new(n);
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn pow(&self, c: f32) -> VBox {
         let func = F::Powf::new(c);
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn exp(&self) -> VBox {
         let func = F::Exp::new();
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn ln(&self) -> VBox {
+        let func = F::Log::new();
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn sin(&self) -> VBox {
+        let func = F::Sin::new();
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn cos(&self) -> VBox {
+        let func = F::Cos::new();
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn tanh(&self) -> VBox {
+        let func = F::Tanh::new();
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn clip(&self, lowerbound: f32, upperbound: f32) -> VBox {
+        let func = F::Clip::new(lowerbound, upperbound);
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn reshape(&self, shape: Vec<usize>) -> VBox {
         let func = F::Reshape::new(self.get_shape(), shape);
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn transpose(&self) -> VBox {
-        let func = F::Transpose::new();
-        F::call(func, &[self.clone()])
+        let axes = (0..self.get_shape().len()).rev().collect();
+        let func = F::Transpose::new(axes);
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn transpose_axes(&self, axes: &[usize]) -> VBox {
+        let func = F::Transpose::new(axes.to_vec());
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn get_item(&self, slices: &[Index]) -> VBox {
+        let func = F::GetItem::new(slices.to_vec(), self.get_shape());
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn sum(&self, axes: Option<&[usize]>, keepdims: bool) -> VBox {
+        let func = F::Sum::new(axes.map(<[usize]>::to_vec), keepdims, self.get_shape());
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn mean(&self, axes: Option<&[usize]>, keepdims: bool) -> VBox {
+        let func = F::Mean::new(axes.map(<[usize]>::to_vec), keepdims, self.get_shape());
+        F::call(func, std::slice::from_ref(self))
+    }
+
+    pub fn max(&self, axes: Option<&[usize]>, keepdims: bool) -> VBox {
+        let func = F::Max::new(axes.map(<[usize]>::to_vec), keepdims);
+        F::call(func, std::slice::from_ref(self))
     }
 
-    pub fn sum(&self) -> VBox {
-        let func = F::Sum::new(self.get_shape());
-        F::call(func, &[self.clone()])
+    pub fn min(&self, axes: Option<&[usize]>, keepdims: bool) -> VBox {
+        let func = F::Min::new(axes.map(<[usize]>::to_vec), keepdims);
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn sum_to(&self, shape: &[usize]) -> VBox {
         let func = F::SumTo::new(self.get_shape(), shape.to_vec());
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn broadcast_to(&self, shape: &[usize]) -> VBox {
         let func = F::BroadcastTo::new(self.get_shape(), shape.to_vec());
-        F::call(func, &[self.clone()])
+        F::call(func, std::slice::from_ref(self))
     }
 
     pub fn matmul(&self, rhs: &VBox) -> VBox {
@@ -3,11 +3,15 @@ use std::{
     cell::RefCell,
     collections::{BinaryHeap, HashSet},
     hash::Hash,
+    path::Path,
     rc::Rc,
 };
 
 use super::{Variable, WeakVBox};
 use crate::array::Array;
+use crate::config;
+use crate::dot;
+use crate::error::Result;
 
 #[derive(Clone)]
 pub struct VBox(Rc<RefCell<Variable>>);
@@ -29,17 +33,38 @@ impl Hash for VBox {
 impl VBox {
     pub fn new(array: Array) -> VBox {
         VBox(Rc::new(RefCell::new(Variable {
+            name: None,
             array,
             grad: None,
+            tangent: None,
             creator: None,
             generation: 0,
         })))
     }
 
+    pub fn with_name(self, name: &str) -> VBox {
+        self.set_name(name);
+        self
+    }
+
     pub fn from_rc(rc: Rc<RefCell<Variable>>) -> VBox {
         VBox(rc)
     }
 
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    pub fn get_name(&self) -> Option<String> {
+        let v = self.0.as_ref();
+        v.borrow().name.clone()
+    }
+
+    pub fn set_name(&self, name: &str) {
+        let v = self.0.as_ref();
+        v.borrow_mut().name = Some(name.to_string());
+    }
+
     pub fn get_array(&self) -> Array {
         let v = self.0.as_ref();
         v.borrow().array.clone()
@@ -51,15 +76,37 @@ impl VBox {
     }
 
     pub fn get_grad(&self) -> Array {
-        let v = self.0.as_ref();
-        v.borrow().grad.clone().unwrap()
+        self.get_grad_var().get_array()
     }
 
     pub fn get_option_grad(&self) -> Option<Array> {
+        self.get_option_grad_var().map(|g| g.get_array())
+    }
+
+    pub fn get_grad_var(&self) -> VBox {
+        self.get_option_grad_var().unwrap()
+    }
+
+    pub fn get_option_grad_var(&self) -> Option<VBox> {
         let v = self.0.as_ref();
         v.borrow().grad.clone()
     }
 
+    pub fn get_tangent(&self) -> Option<Array> {
+        let v = self.0.as_ref();
+        v.borrow().tangent.clone()
+    }
+
+    pub fn set_tangent(&self, tangent: Array) {
+        let v = self.0.as_ref();
+        v.borrow_mut().tangent = Some(tangent);
+    }
+
+    pub fn clear_tangent(&self) {
+        let v = self.0.as_ref();
+        v.borrow_mut().tangent = None;
+    }
+
     pub fn get_creator(&self) -> Option<FuncBox> {
         self.0.clone().borrow().creator.clone()
     }
@@ -74,11 +121,12 @@ impl VBox {
     }
 
     pub fn set_grad(&self, grad: Array) {
+        self.set_grad_var(VBox::new(grad));
+    }
+
+    pub fn set_grad_var(&self, grad: VBox) {
         let v = self.0.as_ref();
-        match &mut v.borrow_mut().grad {
-            Some(grad_old) => grad_old.set_data(grad),
-            x @ None => *x = Some(grad),
-        };
+        v.borrow_mut().grad = Some(grad);
     }
 
     pub fn clear_grad(&self) {
@@ -98,6 +146,10 @@ impl VBox {
     }
 
     pub fn backward_with_option(&self, retain_grad: bool) {
+        self.backward_with_options(retain_grad, false);
+    }
+
+    pub fn backward_with_options(&self, retain_grad: bool, create_graph: bool) {
         if self.get_option_grad().is_none() {
             self.set_grad(Array::ones(&self.get_shape()));
         }
@@ -113,14 +165,15 @@ impl VBox {
 
         while let Some(f) = funcs.pop() {
             let x = f.get_inputs();
-            let gy = f.get_output().get_grad();
+            let gy = f.get_output().get_grad_var();
+            let _guard = config::using_config("enable_backprop", create_graph);
             let gxs = f.backward(gy);
 
-            for (x, gx) in x.iter().zip(gxs.into_iter()) {
-                if let Some(gx_old) = x.get_option_grad() {
-                    x.set_grad(gx_old + &gx)
+            for (x, gx) in x.iter().zip(gxs) {
+                if let Some(gx_old) = x.get_option_grad_var() {
+                    x.set_grad_var(gx_old + gx)
                 } else {
-                    x.set_grad(gx);
+                    x.set_grad_var(gx);
                 }
 
                 if let Some(x_creator) = x.get_creator() {
@@ -137,6 +190,14 @@ impl VBox {
         }
     }
 
+    pub fn to_dot(&self) -> String {
+        dot::get_dot_graph(self)
+    }
+
+    pub fn plot_dot_graph(&self, path: impl AsRef<Path>) -> Result<()> {
+        dot::plot_dot_graph(self, path)
+    }
+
     pub fn downgrade(self) -> WeakVBox {
         WeakVBox::new(Rc::downgrade(&self.0))
     }
@@ -145,6 +206,9 @@ impl VBox {
 impl std::fmt::Display for VBox {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut string = format!("Variable({}", self.get_array().to_string(9));
+        if let Some(name) = self.get_name() {
+            string += &format!(",\n   name: {}", name);
+        }
         match self.get_option_grad() {
             None => {}
             Some(g) => string += &format!(",\n   grad: {}", g.to_string(9)),
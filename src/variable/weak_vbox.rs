@@ -1,5 +1,6 @@
 use super::{VBox, Variable};
 use crate::array::Array;
+use crate::error::{DezeroError, Result};
 use std::{cell::RefCell, rc::Weak};
 
 #[derive(Clone)]
@@ -11,7 +12,14 @@ impl WeakVBox {
     }
 
     pub fn upgrade(&self) -> VBox {
-        VBox::from_rc(self.0.upgrade().unwrap())
+        self.try_upgrade().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_upgrade(&self) -> Result<VBox> {
+        self.0
+            .upgrade()
+            .map(VBox::from_rc)
+            .ok_or(DezeroError::DanglingNode)
     }
 
     pub fn get_array(&self) -> Array {
@@ -24,6 +32,11 @@ impl WeakVBox {
         v.get_grad()
     }
 
+    pub fn get_grad_var(&self) -> VBox {
+        let v = self.upgrade();
+        v.get_grad_var()
+    }
+
     pub fn clear_grad(&self) {
         let v = self.upgrade();
         v.clear_grad();
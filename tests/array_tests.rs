@@ -0,0 +1,400 @@
+extern crate dezero;
+
+use dezero::{
+    array::{load_npz, save_npz, Array, CsvOptions, Index, Missing, NpyArray},
+    array0, array1, array2, s, DezeroError,
+};
+
+#[test]
+fn transpose_nd() {
+    let x = array1!(0..24).reshape(&[2, 3, 4]);
+    let y = x.transpose();
+
+    assert_eq!(y.get_shape(), &[4, 3, 2]);
+    assert_eq!(y.transpose(), x);
+}
+
+#[test]
+fn permute() {
+    let x = array1!(0..6).reshape(&[1, 2, 3]);
+    let y = x.permute(&[2, 0, 1]);
+
+    assert_eq!(y, array1!([0, 3, 1, 4, 2, 5]).reshape(&[3, 1, 2]));
+    assert_eq!(y.permute(&[1, 2, 0]), x);
+    assert_eq!(
+        Array::ones(&[2, 3, 4, 5])
+            .transpose_axes(&[0, 2, 3, 1])
+            .get_shape(),
+        &[2, 4, 5, 3]
+    );
+}
+
+#[test]
+#[should_panic]
+fn permute_invalid_axes() {
+    array2!([[1, 2], [3, 4]]).permute(&[0, 0]);
+}
+
+#[test]
+fn transpose_is_view() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let y = x.transpose();
+
+    assert!(!y.is_contiguous());
+    assert_eq!(y.get_strides(), &[1, 3]);
+    assert_eq!(y.contiguous().get_strides(), &[2, 1]);
+    assert_eq!(y.clone().reshape(&[6]), array1!([0, 3, 1, 4, 2, 5]));
+    assert_eq!(y.get_data().to_vec(), vec![0., 3., 1., 4., 2., 5.]);
+}
+
+#[test]
+fn broadcast_is_view() {
+    let x = array1!(0..3).broadcast_to(&[4, 3]);
+
+    assert_eq!(x.get_strides(), &[0, 1]);
+    assert_eq!(x, array2!([[0, 1, 2]; 4]));
+    assert_eq!(x.sum_to(&[3]), array1!([0, 4, 8]));
+}
+
+#[test]
+fn slice_axis() {
+    let x = array1!(0..12).reshape(&[3, 4]);
+
+    assert_eq!(x.slice_axis(1, 1, 4, 2), array2!([[1, 3], [5, 7], [9, 11]]));
+    assert_eq!(x.slice_axis(0, 2, 3, 1), array2!([[8, 9, 10, 11]]));
+    assert_eq!(x.slice_axis(0, 1, 1, 1).get_shape(), &[0, 4]);
+}
+
+#[test]
+fn ops_on_views() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let y = &x.transpose() + &array1!([10, 20]);
+
+    assert_eq!(y, array2!([[10, 23], [11, 24], [12, 25]]));
+    assert_eq!(
+        x.transpose().matmul(&x),
+        x.transpose().contiguous().matmul(&x)
+    );
+}
+
+#[test]
+fn slice_with_indices() {
+    let x = array1!(0..24).reshape(&[2, 3, 4]);
+
+    assert_eq!(x.slice(&s![1]), array1!(12..24).reshape(&[3, 4]));
+    assert_eq!(x.slice(&s![.., 0, 1..3]), array2!([[1, 2], [13, 14]]));
+    assert_eq!(
+        x.slice(&[Index::full(), Index::step(.., 2), Index::At(3)]),
+        array2!([[3, 11], [15, 23]])
+    );
+    assert_eq!(x.slice(&s![1, 2..]).get_shape(), &[1, 4]);
+}
+
+#[test]
+fn fancy_index() {
+    let x = array1!(0..12).reshape(&[4, 3]);
+
+    assert_eq!(
+        x.slice(&s![vec![3, 0, 3]]),
+        array2!([[9, 10, 11], [0, 1, 2], [9, 10, 11]])
+    );
+    assert_eq!(x.slice(&s![1..3, vec![2, 0]]), array2!([[5, 3], [8, 6]]));
+
+    let empty = Array::<f32>::zeros(&[2, 0]);
+    assert_eq!(empty.slice(&s![vec![0, 1, 0]]).get_shape(), &[3, 0]);
+    assert_eq!(empty.slice(&s![.., vec![]]).get_shape(), &[2, 0]);
+}
+
+#[test]
+fn add_at() {
+    let x = Array::zeros(&[3, 2]);
+
+    assert_eq!(
+        x.add_at(&s![vec![0, 2, 0]], &array2!([[1, 2], [3, 4], [5, 6]])),
+        array2!([[6, 8], [0, 0], [3, 4]])
+    );
+    assert_eq!(
+        x.add_at(&s![.., 1], &array1!([1, 2, 3])),
+        array2!([[0, 1], [0, 2], [0, 3]])
+    );
+}
+
+#[test]
+fn concat_stack_split() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let y = array1!(6..10).reshape(&[2, 2]);
+    let z = Array::concat(&[&x, &y], 1);
+
+    assert_eq!(z, array2!([[0, 1, 2, 6, 7], [3, 4, 5, 8, 9]]));
+    assert_eq!(z.split(&[3, 2], 1), vec![x.clone(), y]);
+    assert_eq!(
+        Array::concat(&[&x, &x.transpose().transpose()], 0),
+        array2!([[0, 1, 2], [3, 4, 5], [0, 1, 2], [3, 4, 5]])
+    );
+
+    let s = Array::stack(&[&x, &(&x + 10.)], 1);
+    assert_eq!(s.get_shape(), &[2, 2, 3]);
+    assert_eq!(s.slice(&s![.., 1]), &x + 10.);
+}
+
+#[test]
+fn reductions() {
+    let x = array2!([[1, 5, 2], [4, 3, 6]]);
+
+    assert_eq!(x.sum(None, false), array0!(21));
+    assert_eq!(x.sum(Some(&[0]), false), array1!([5, 8, 8]));
+    assert_eq!(x.sum(Some(&[1]), true), array2!([[8], [13]]));
+    assert_eq!(x.sum(Some(&[0, 1]), true), array2!([[21]]));
+    assert_eq!(x.mean(Some(&[0]), false), array1!([2.5, 4., 4.]));
+    assert_eq!(x.max(Some(&[1]), false), array1!([5, 6]));
+    assert_eq!(x.min(None, false), array0!(1));
+    assert_eq!(x.prod(Some(&[1]), false), array1!([10, 72]));
+    assert_eq!(x.var(Some(&[0]), false), array1!([2.25, 1., 4.]));
+    assert_eq!(x.std(Some(&[0]), false), array1!([1.5, 1., 2.]));
+}
+
+#[test]
+fn arg_reductions() {
+    let x = array2!([[1, 5, 2], [4, 3, 6]]);
+
+    assert_eq!(x.argmax(Some(1)), Array::new(vec![1, 2], vec![2]));
+    assert_eq!(x.argmin(Some(0)), Array::new(vec![0, 1, 0], vec![3]));
+    assert_eq!(x.argmax(None), Array::new(vec![5], vec![]));
+    assert_eq!(
+        x.transpose().argmax(Some(0)),
+        Array::new(vec![1, 2], vec![2])
+    );
+    assert_eq!(array1!([3, 1, 3]).argmax_mask(None), array1!([1, 0, 0]));
+}
+
+#[test]
+fn dtypes() {
+    let x: Array<f64> = Array::new(vec![1., 2., 3., 4.], vec![2, 2]);
+    assert_eq!(x.get_data()[1], 2f64);
+    assert!((1. / &x)
+        .sum(None, false)
+        .all_close(&Array::full(&[], 25. / 12.), 1e-12));
+    assert_eq!(x.matmul(&x).astype::<f32>(), array2!([[7, 10], [15, 22]]));
+
+    let i = Array::new(vec![3i32, -1, 4], vec![3]);
+    assert_eq!(&i * 2 - 1, Array::new(vec![5, -3, 7], vec![3]));
+    assert_eq!(i.max(None, false), Array::full(&[], 4));
+    assert_eq!(
+        array1!([0.7, 2.2]).astype::<i64>(),
+        Array::new(vec![0i64, 2], vec![2])
+    );
+}
+
+#[test]
+fn bool_masks() {
+    let x = array1!([-1, 0, 2, 3]);
+    let mask = x.gt(&Array::full(&[], 0.));
+
+    assert_eq!(mask, Array::new(vec![false, false, true, true], vec![4]));
+    assert_eq!(&x * mask.astype(), array1!([0, 0, 2, 3]));
+    assert_eq!(
+        x.le(&array1!([0, 0, 0, 0]))
+            .astype::<i32>()
+            .sum(None, false),
+        Array::full(&[], 2)
+    );
+    assert_eq!(
+        Array::new(vec![2i64, 0], vec![2]).one_hot(3),
+        array2!([[0, 0, 1], [1, 0, 0]])
+    );
+}
+
+#[test]
+fn try_variants() {
+    assert!(matches!(
+        Array::try_new(vec![1., 2., 3.], vec![2, 2]),
+        Err(DezeroError::ShapeMismatch { .. })
+    ));
+    assert!(matches!(
+        Array::ones(&[2, 3]).try_reshape(&[4]),
+        Err(DezeroError::ShapeMismatch { op: "reshape", .. })
+    ));
+    assert!(matches!(
+        Array::ones(&[2, 3]).try_broadcast_to(&[2, 2]),
+        Err(DezeroError::Broadcast { .. })
+    ));
+    assert!(Array::ones(&[2, 3]).try_sum_to(&[2, 2]).is_err());
+
+    let err = Array::ones(&[2, 3])
+        .try_matmul(&Array::ones(&[2, 3]))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "matmul: incompatible shapes [2, 3] and [2, 3]"
+    );
+}
+
+#[test]
+fn read_csv_errors() {
+    let path = std::env::temp_dir().join("dezero_read_csv_errors.csv");
+    let path = path.to_str().unwrap();
+
+    std::fs::write(path, "1,2\n3,x\n").unwrap();
+    assert!(matches!(
+        Array::try_read_csv(path),
+        Err(DezeroError::Parse {
+            line: 2,
+            column: Some(2),
+            ..
+        })
+    ));
+
+    std::fs::write(path, "1,2\n3,4\n").unwrap();
+    assert_eq!(
+        Array::try_read_csv(path).unwrap(),
+        array2!([[1, 2], [3, 4]])
+    );
+
+    std::fs::remove_file(path).unwrap();
+    assert!(matches!(Array::try_read_csv(path), Err(DezeroError::Io(_))));
+}
+
+#[test]
+fn npy_roundtrip() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let bytes = x.to_npy_bytes();
+    let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }";
+    assert_eq!(&bytes[..10], b"\x93NUMPY\x01\x00\x76\x00");
+    assert_eq!(&bytes[10..10 + header.len()], header.as_bytes());
+    assert_eq!(bytes.len(), 128 + 6 * 4);
+    assert_eq!(bytes[127], b'\n');
+    assert_eq!(Array::from_npy_bytes(&bytes).unwrap(), x);
+
+    let t = x.transpose();
+    assert_eq!(Array::from_npy_bytes(&t.to_npy_bytes()).unwrap(), t);
+
+    let path = std::env::temp_dir().join("dezero_npy_roundtrip.npy");
+    let y = Array::new(vec![1.5f64, -2., 1e-300], vec![3]);
+    y.save_npy(&path).unwrap();
+    assert_eq!(Array::<f64>::load_npy(&path).unwrap(), y);
+
+    let s = Array::new(vec![7i64], vec![]);
+    assert_eq!(Array::from_npy_bytes(&s.to_npy_bytes()).unwrap(), s);
+    let b = Array::new(vec![true, false], vec![2]);
+    assert_eq!(Array::from_npy_bytes(&b.to_npy_bytes()).unwrap(), b);
+
+    assert!(matches!(
+        Array::<i32>::from_npy_bytes(&x.to_npy_bytes()),
+        Err(DezeroError::Format(_))
+    ));
+    assert!(matches!(
+        Array::<f32>::from_npy_bytes(&bytes[..130]),
+        Err(DezeroError::Format(_))
+    ));
+}
+
+#[test]
+fn npz_roundtrip() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let y = array0!(3.5);
+    let labels = Array::new(vec![2i64, 0, 1], vec![3]);
+    let mask = Array::new(vec![true, false], vec![2]);
+    let path = std::env::temp_dir().join("dezero_npz_roundtrip.npz");
+    save_npz(
+        &path,
+        &[
+            ("x", x.clone().into()),
+            ("y", y.clone().into()),
+            ("labels", labels.clone().into()),
+            ("mask", mask.clone().into()),
+        ],
+    )
+    .unwrap();
+
+    let arrays = load_npz(&path).unwrap();
+    assert_eq!(arrays.len(), 4);
+    assert_eq!(arrays["x"], NpyArray::F32(x));
+    assert_eq!(arrays["y"].clone().into_array::<f32>().unwrap(), y);
+    assert_eq!(arrays["labels"], NpyArray::I64(labels));
+    assert_eq!(arrays["labels"].dtype(), "i64");
+    assert_eq!(arrays["mask"].get_shape(), &[2]);
+    assert!(matches!(
+        arrays["mask"].clone().into_array::<f32>(),
+        Err(DezeroError::Format(_))
+    ));
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[100] ^= 1;
+    std::fs::write(&path, bytes).unwrap();
+    assert!(matches!(load_npz(&path), Err(DezeroError::Format(_))));
+}
+
+#[test]
+fn csv_options() {
+    let path = std::env::temp_dir().join("dezero_csv_options.csv");
+    let path = path.to_str().unwrap();
+
+    std::fs::write(path, "label;a;b;c\n1;0.5;;2\n0;1.5;3;4\n").unwrap();
+    let options = CsvOptions::new()
+        .delimiter(';')
+        .has_header(true)
+        .label_column(0)
+        .missing(Missing::Fill(-1.));
+    let (x, t) = Array::try_read_csv_with(path, &options).unwrap();
+    assert_eq!(x, array2!([[0.5, -1., 2.], [1.5, 3., 4.]]));
+    assert_eq!(t.unwrap(), array1!([1, 0]));
+
+    let options = options.columns(&[3, 1]);
+    let (x, _) = Array::try_read_csv_with(path, &options).unwrap();
+    assert_eq!(x, array2!([[2., 0.5], [4., 1.5]]));
+
+    let options = options.columns(&[0, 3]);
+    let (x, t) = Array::try_read_csv_with(path, &options).unwrap();
+    assert_eq!(x, array2!([[2.], [4.]]));
+    assert_eq!(t.unwrap(), array1!([1, 0]));
+
+    let options = options.columns(&[2, 1]).missing(Missing::SkipRow);
+    let (x, t) = Array::try_read_csv_with(path, &options).unwrap();
+    assert_eq!(x, array2!([[3., 1.5]]));
+    assert_eq!(t.unwrap(), array1!([0]));
+
+    let options = CsvOptions::new().delimiter(';').has_header(true);
+    assert!(matches!(
+        Array::try_read_csv_with(path, &options),
+        Err(DezeroError::Parse {
+            line: 2,
+            column: Some(3),
+            ..
+        })
+    ));
+
+    std::fs::write(path, "1,2\n3\n").unwrap();
+    let err = Array::try_read_csv(path).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "parse error at line 2: expected 2 columns, found 1"
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn write_csv_nd() {
+    let path = std::env::temp_dir().join("dezero_write_csv_nd.csv");
+    let path = path.to_str().unwrap();
+
+    let x = array1!(0..8).reshape(&[2, 2, 2]);
+    x.try_write_csv(path, &CsvOptions::new().delimiter('\t'))
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(path).unwrap(),
+        "0\t1\t2\t3\n4\t5\t6\t7"
+    );
+
+    let options = CsvOptions::new().has_header(true);
+    x.try_write_csv(path, &options).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(path).unwrap(),
+        "0,1,2,3\n0,1,2,3\n4,5,6,7"
+    );
+    let (y, _) = Array::try_read_csv_with(path, &options).unwrap();
+    assert_eq!(y, x.reshape(&[2, 4]));
+
+    array1!([1, 2, 3]).write_csv(path);
+    assert_eq!(Array::read_csv(path), array2!([[1], [2], [3]]));
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,220 @@
+extern crate dezero;
+
+use dezero::{
+    array::{Array, Index},
+    autograd::{gradcheck, GradMismatch},
+    functions::{self as F, Function},
+    s, test_mode,
+    variable::{VBox, WeakVBox},
+};
+
+fn sample(shape: &[usize], seed: f32) -> Array {
+    let size = shape.iter().product::<usize>();
+    let data = (0..size)
+        .map(|i| ((i as f32 + 1.) * 0.77 + seed).sin())
+        .collect();
+    Array::new(data, shape.to_vec())
+}
+
+fn positive(shape: &[usize], seed: f32) -> Array {
+    sample(shape, seed) * 0.4 + 0.5
+}
+
+fn check(f: impl Fn(&[VBox]) -> VBox, inputs: &[Array]) {
+    if let Err(report) = gradcheck(f, inputs, 1e-3, 1e-4, 1e-4) {
+        panic!("{}", report);
+    }
+}
+
+#[test]
+fn arithmetic() {
+    let x = [sample(&[2, 3], 0.), positive(&[2, 3], 1.)];
+    check(|x| &x[0] + &x[1], &x);
+    check(|x| &x[0] - &x[1], &x);
+    check(|x| &x[0] * &x[1], &x);
+    check(|x| &x[0] / &x[1], &x);
+    check(|x| -&x[0], &x);
+    check(|x| 2. * &x[0] + &x[1] / 3., &x);
+
+    let b = [sample(&[2, 3], 0.), positive(&[3], 2.)];
+    check(|x| &x[0] + &x[1], &b);
+    check(|x| &x[0] - &x[1], &b);
+    check(|x| &x[0] * &x[1], &b);
+    check(|x| &x[0] / &x[1], &b);
+}
+
+#[test]
+fn elementwise() {
+    let x = [sample(&[2, 3], 0.)];
+    let p = [positive(&[2, 3], 0.)];
+    check(|x| x[0].powi(3), &x);
+    check(|x| x[0].pow(1.5), &p);
+    check(|x| x[0].exp(), &x);
+    check(|x| x[0].ln(), &p);
+    check(|x| x[0].sin(), &x);
+    check(|x| x[0].cos(), &x);
+    check(|x| x[0].tanh(), &x);
+    check(|x| x[0].clip(-0.5, 0.5), &x);
+}
+
+#[test]
+fn shape_ops() {
+    let x = [sample(&[2, 3, 4], 0.)];
+    check(|x| x[0].reshape(vec![6, 4]), &x);
+    check(|x| x[0].transpose(), &x);
+    check(|x| x[0].transpose_axes(&[1, 2, 0]), &x);
+    check(|x| x[0].sum_to(&[3, 1]), &x);
+    check(|x| x[0].broadcast_to(&[2, 2, 3, 4]), &x);
+    check(|x| x[0].get_item(&s![1, 1.., Index::step(.., 2)]), &x);
+    check(|x| x[0].get_item(&s![.., vec![0, 2, 0]]), &x);
+}
+
+#[test]
+fn reductions() {
+    let x = [sample(&[2, 3, 4], 0.)];
+    check(|x| x[0].sum(None, false), &x);
+    check(|x| x[0].sum(Some(&[0, 2]), true), &x);
+    check(|x| x[0].mean(Some(&[1]), false), &x);
+    check(|x| x[0].max(Some(&[2]), false), &x);
+    check(|x| x[0].min(None, true), &x);
+}
+
+#[test]
+fn join_and_split() {
+    let x = [sample(&[2, 3], 0.), sample(&[2, 1], 1.)];
+    check(|x| F::concat(x, 1), &x);
+    check(|x| F::stack(&[x[0].clone(), x[0].clone()], 0), &x[..1]);
+    check(
+        |x| {
+            let parts = F::split(&x[0], &[1, 2], 1);
+            &parts[0] * &parts[1]
+        },
+        &x[..1],
+    );
+}
+
+#[test]
+fn matmul() {
+    check(
+        |x| x[0].matmul(&x[1]),
+        &[sample(&[2, 3], 0.), sample(&[3, 4], 1.)],
+    );
+    check(
+        |x| x[0].matmul(&x[1]),
+        &[sample(&[2, 2, 3], 0.), sample(&[3, 4], 1.)],
+    );
+    check(
+        |x| x[0].matmul(&x[1]),
+        &[sample(&[3], 0.), sample(&[3, 2], 1.)],
+    );
+    check(
+        |x| x[0].matmul(&x[1]),
+        &[sample(&[2, 2, 3], 0.), sample(&[3], 1.)],
+    );
+    check(
+        |x| F::linear(&x[0], &x[1], Some(&x[2])),
+        &[sample(&[2, 3], 0.), sample(&[3, 4], 1.), sample(&[4], 2.)],
+    );
+    check(
+        |x| F::linear(&x[0], &x[1], None),
+        &[sample(&[2, 3], 0.), sample(&[3, 4], 1.)],
+    );
+}
+
+#[test]
+fn activations_and_losses() {
+    let x = [sample(&[2, 3], 0.)];
+    check(|x| F::sigmoid(&x[0]), &x);
+    check(|x| F::relu(&x[0]), &x);
+    check(|x| F::softmax(&x[0], 1) * &x[0], &x);
+    check(
+        |x| F::mean_squared_error(&x[0], &x[1]),
+        &[sample(&[4, 1], 0.), sample(&[4, 1], 1.)],
+    );
+    check(
+        |x| F::cross_entropy_loss(&x[0], &x[1]),
+        &[positive(&[2, 3], 0.), positive(&[2, 3], 1.)],
+    );
+
+    let _test_mode = test_mode();
+    check(|x| F::dropout(&x[0], 0.5), &x);
+}
+
+#[test]
+fn reports_mismatch() {
+    let x = Array::new(vec![1., 2.], vec![2]);
+    let result = gradcheck(
+        |x| &x[0] * &VBox::new(x[0].get_array()),
+        &[x],
+        1e-2,
+        1e-3,
+        1e-3,
+    );
+    let report = result.unwrap_err();
+
+    assert_eq!(report.mismatches.len(), 2);
+    let GradMismatch {
+        input,
+        index,
+        analytic,
+        numeric,
+    } = report.mismatches[1];
+    assert_eq!((input, index), (0, 1));
+    assert_eq!(analytic, 2.);
+    assert!((numeric - 4.).abs() < 1e-3);
+}
+
+// Square whose backward forgets the factor of 2.
+struct BadSquare {
+    inputs: Vec<VBox>,
+    output: Option<WeakVBox>,
+    generation: u32,
+}
+
+impl Function for BadSquare {
+    fn name(&self) -> &'static str {
+        "BadSquare"
+    }
+    fn get_generation(&self) -> u32 {
+        self.generation
+    }
+    fn get_inputs(&self) -> Vec<VBox> {
+        self.inputs.clone()
+    }
+    fn get_output(&self) -> WeakVBox {
+        self.output.clone().unwrap()
+    }
+    fn set_generation(&mut self, gen: u32) {
+        self.generation = gen;
+    }
+    fn set_inputs(&mut self, inputs: Vec<VBox>) {
+        self.inputs = inputs;
+    }
+    fn set_output(&mut self, output: WeakVBox) {
+        self.output = Some(output);
+    }
+    fn forward(&self, x: Vec<Array>) -> Array {
+        x[0].powi(2)
+    }
+    fn backward(&self, gy: VBox) -> Vec<VBox> {
+        vec![gy * &self.inputs[0]]
+    }
+}
+
+#[test]
+fn catches_wrong_backward() {
+    let bad_square = |x: &[VBox]| {
+        let func = BadSquare {
+            inputs: Vec::new(),
+            output: None,
+            generation: 0,
+        };
+        F::call(func, x)
+    };
+    let report = gradcheck(bad_square, &[sample(&[2, 3], 0.)], 1e-3, 1e-4, 1e-4).unwrap_err();
+
+    assert_eq!(report.mismatches.len(), 6);
+    for m in &report.mismatches {
+        assert!((m.numeric - 2. * m.analytic).abs() < 1e-3);
+    }
+}
@@ -1,6 +1,6 @@
 extern crate dezero;
 
-use dezero::{array::Array, array0, array1};
+use dezero::{array::Array, array0, array1, var};
 
 #[test]
 fn matmul1() {
@@ -30,6 +30,15 @@ fn matmul3() {
     assert_eq!(x.matmul(&x), array0!(55));
 }
 
+#[test]
+fn matmul_vector() {
+    let x = array1!(0..6).reshape(&[2, 3]);
+    let v = array1!([1, 2, 3]);
+
+    assert_eq!(x.matmul(&v), array1!([8, 26]));
+    assert_eq!(array1!([1, 2]).matmul(&x), array1!([6, 9, 12]));
+}
+
 #[test]
 fn matmul4() {
     let a = Array::ones(&[9, 5, 7, 4]);
@@ -37,3 +46,69 @@ fn matmul4() {
 
     assert_eq!(a.matmul(&c).get_shape(), &[9, 5, 7, 3])
 }
+
+#[test]
+fn matmul_broadcast_stack() {
+    let x = array1!(0..8).reshape(&[2, 1, 2, 2]);
+    let y = array1!(0..12).reshape(&[3, 2, 2]);
+    let z = x.matmul(&y);
+
+    assert_eq!(z.get_shape(), &[2, 3, 2, 2]);
+    assert_eq!(
+        z.slice_axis(0, 1, 2, 1)
+            .slice_axis(1, 2, 3, 1)
+            .reshape(&[2, 2]),
+        array1!([4, 5, 6, 7])
+            .reshape(&[2, 2])
+            .matmul(&array1!(8..12).reshape(&[2, 2]))
+    );
+}
+
+#[test]
+fn matmul_large() {
+    let (l, m, n) = (70, 80, 90);
+    let x = Array::rand(&[2, l, m]);
+    let y = Array::rand(&[m, n]);
+    let z = x.matmul(&y);
+
+    let (xd, yd, zd) = (x.get_data(), y.get_data(), z.get_data());
+    for b in 0..2 {
+        for i in 0..l {
+            for j in 0..n {
+                let expected: f32 = (0..m)
+                    .map(|k| xd[(b * l + i) * m + k] * yd[k * n + j])
+                    .sum();
+                assert!((zd[(b * l + i) * n + j] - expected).abs() < 1e-3);
+            }
+        }
+    }
+}
+
+#[test]
+fn matmul_broadcast_lhs() {
+    let x = Array::rand(&[5, 70]);
+    let y = Array::rand(&[3, 70, 6]);
+    let z = x.matmul(&y);
+
+    assert_eq!(z.get_shape(), &[3, 5, 6]);
+    for b in 0..3 {
+        let expected = x.matmul(&y.slice_axis(0, b, b + 1, 1).reshape(&[70, 6]));
+        assert!(z
+            .slice_axis(0, b, b + 1, 1)
+            .reshape(&[5, 6])
+            .all_close(&expected, 1e-4));
+    }
+}
+
+#[test]
+fn matmul_vector_backward() {
+    let x = var!(array1!([0., 1., 2., 3., 4., 5.]).reshape(&[2, 3]));
+    let v = var!(array1!([1., 2., 3.]));
+    x.matmul(v).sum(None, false).backward();
+
+    assert_eq!(
+        x.get_grad(),
+        array1!([1., 2., 3., 1., 2., 3.]).reshape(&[2, 3])
+    );
+    assert_eq!(v.get_grad(), array1!([3., 5., 7.]));
+}
@@ -0,0 +1,392 @@
+extern crate dezero;
+
+use dezero::{
+    array::Array,
+    array0, array2, functions as F,
+    layers::{Linear, Model},
+    optimizers::{
+        clip_grad_norm, AdaDelta, AdaGrad, Adam, AdamW, ClipGrad, ClipGradValue, LineSearch,
+        Momentum, Nesterov, Optimizer, ParamGroup, RMSProp, WeightDecay, LBFGS, SGD,
+    },
+    schedulers::{ExponentialLR, LrScheduler},
+    state_dict::{self, StateDict},
+    var,
+    variable::VBox,
+    DezeroError,
+};
+
+fn linear_model(w: f32) -> Model {
+    let model = Model::new(Linear::new(1, false));
+    let mut state_dict = StateDict::new();
+    state_dict.insert("w".to_string(), array2!([[w]]));
+    model.load_state_dict(&state_dict).unwrap();
+    model
+}
+
+fn step(model: &Model, optimizer: &mut dyn Optimizer) -> f32 {
+    let x = var!(array2!([[1.], [2.], [3.]]));
+    let t = var!(array2!([[2.], [4.], [6.]]));
+    let loss = F::mean_squared_error(&model.call(x), t);
+    model.clear_grads();
+    loss.backward();
+    optimizer.update();
+    loss.get_array().to_vec()[0]
+}
+
+fn weight(model: &Model) -> f32 {
+    model.get_params()[0].get_array().to_vec()[0]
+}
+
+#[test]
+fn adam_first_step() {
+    let model = linear_model(1.);
+    let mut optimizer = Adam::new(0.1, model.clone());
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 1.1).abs() < 1e-6);
+
+    let model = linear_model(1.);
+    let mut optimizer = AdamW::new(0.1, 0.5, model.clone()).eps(1e-6);
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 1.05).abs() < 1e-6);
+}
+
+#[test]
+fn nesterov_step() {
+    let model = linear_model(1.);
+    let mut optimizer = Nesterov::new(0.01, 0.9, model.clone());
+    // grad = -28/3: v = 0.28/3, w = 1 + 0.9 * v + 0.28/3
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - (1. + 1.9 * 0.28 / 3.)).abs() < 1e-6);
+}
+
+fn optimizer(name: &str, model: Model) -> Box<dyn Optimizer> {
+    match name {
+        "SGD" => Box::new(SGD::new(0.05, model)),
+        "Momentum" => Box::new(Momentum::new(0.02, 0.9, model)),
+        "Nesterov" => Box::new(Nesterov::new(0.02, 0.9, model)),
+        "AdaGrad" => Box::new(AdaGrad::new(0.5, model)),
+        "RMSProp" => Box::new(RMSProp::new(0.05, 0.9, model)),
+        "AdaDelta" => Box::new(AdaDelta::new(0.9, model).eps(1e-2)),
+        "Adam" => Box::new(Adam::new(0.1, model)),
+        "AdamW" => Box::new(AdamW::new(0.1, 1e-3, model)),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn optimizers_converge() {
+    let names = [
+        "SGD", "Momentum", "Nesterov", "AdaGrad", "RMSProp", "AdaDelta", "Adam", "AdamW",
+    ];
+    for name in names {
+        let model = linear_model(0.);
+        let mut optimizer = optimizer(name, model.clone());
+        for _ in 0..300 {
+            step(&model, optimizer.as_mut());
+        }
+        let loss = step(&model, optimizer.as_mut());
+        assert!(loss < 1e-2, "{} did not converge: loss {}", name, loss);
+    }
+}
+
+#[test]
+fn clip_grad_norm_rescales() {
+    let a = var!(array2!([[0.]]));
+    let b = var!(array2!([[0.]]));
+    a.set_grad(array2!([[3.]]));
+    b.set_grad(array2!([[4.]]));
+    let params = [a.clone(), b.clone()];
+
+    assert!((clip_grad_norm(&params, 10.) - 5.).abs() < 1e-6);
+    assert_eq!(a.get_grad().to_vec(), vec![3.]);
+    assert!((clip_grad_norm(&params, 1.) - 5.).abs() < 1e-6);
+    assert!((a.get_grad().to_vec()[0] - 0.6).abs() < 1e-5);
+    assert!((b.get_grad().to_vec()[0] - 0.8).abs() < 1e-5);
+}
+
+#[test]
+fn optimizer_hooks() {
+    // grad = -28/3 at w = 1
+    let model = linear_model(1.);
+    let mut optimizer = SGD::new(0.1, model.clone());
+    optimizer.add_hook(Box::new(WeightDecay::new(1.)));
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - (1. - 0.1 * (1. - 28. / 3.))).abs() < 1e-5);
+
+    let model = linear_model(1.);
+    let mut optimizer = SGD::new(0.1, model.clone());
+    optimizer.add_hook(Box::new(ClipGrad::new(2.)));
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 1.2).abs() < 1e-5);
+
+    let model = linear_model(1.);
+    let mut optimizer = SGD::new(0.1, model.clone());
+    optimizer.add_hook(Box::new(ClipGradValue::new(0.5)));
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 1.05).abs() < 1e-6);
+
+    let model = linear_model(1.);
+    let mut optimizer = Adam::new(0.1, model.clone());
+    let mut calls = 0;
+    optimizer.add_hook(Box::new(move |params: &[VBox]| {
+        calls += 1;
+        for param in params {
+            param.set_grad(param.get_grad() * 0.);
+        }
+        assert_eq!(calls, 1);
+    }));
+    step(&model, &mut optimizer);
+    assert_eq!(weight(&model), 1.);
+}
+
+#[test]
+fn optimizer_state_resume() {
+    let names = ["Momentum", "AdaDelta", "Adam", "AdamW"];
+    for name in names {
+        let model = linear_model(0.);
+        let mut original = optimizer(name, model.clone());
+        for _ in 0..3 {
+            step(&model, original.as_mut());
+        }
+        let path = std::env::temp_dir().join(format!("dezero_optimizer_{}.bin", name));
+        state_dict::save(&path, &original.state_dict()).unwrap();
+
+        let resumed = linear_model(weight(&model));
+        let mut resumed_optimizer = optimizer(name, resumed.clone());
+        resumed_optimizer.set_lr(1.);
+        resumed_optimizer
+            .load_state_dict(&state_dict::load(&path).unwrap())
+            .unwrap();
+        assert_eq!(resumed_optimizer.lr(), original.lr());
+        for _ in 0..2 {
+            step(&model, original.as_mut());
+            step(&resumed, resumed_optimizer.as_mut());
+        }
+        assert_eq!(weight(&model), weight(&resumed), "{}", name);
+    }
+}
+
+#[test]
+fn optimizer_state_errors() {
+    let model = linear_model(0.);
+    let mut optimizer = Adam::new(0.1, model.clone());
+    step(&model, &mut optimizer);
+    let state = optimizer.state_dict();
+    assert_eq!(
+        state.keys().collect::<Vec<_>>(),
+        ["lr", "ms.0", "t", "vs.0"]
+    );
+
+    let mut bad = state.clone();
+    bad.insert("ms.1".to_string(), array2!([[0.]]));
+    assert!(matches!(
+        optimizer.load_state_dict(&bad),
+        Err(DezeroError::UnexpectedKey(key)) if key == "ms.1"
+    ));
+    let mut bad = state.clone();
+    bad.remove("t");
+    assert!(matches!(
+        optimizer.load_state_dict(&bad),
+        Err(DezeroError::MissingKey(key)) if key == "t"
+    ));
+    let mut bad = state;
+    bad.insert("vs.0".to_string(), array2!([[0., 0.]]));
+    assert!(matches!(
+        optimizer.load_state_dict(&bad),
+        Err(DezeroError::ParamShape { .. })
+    ));
+}
+
+#[test]
+fn param_groups() {
+    let model = Model::new(Linear::new(1, true));
+    let mut state_dict = StateDict::new();
+    state_dict.insert("w".to_string(), array2!([[1.]]));
+    state_dict.insert("b".to_string(), Array::zeros(&[1]));
+    model.load_state_dict(&state_dict).unwrap();
+    let (weights, biases): (Vec<_>, Vec<_>) = model
+        .named_params()
+        .into_iter()
+        .partition(|(name, _)| name == "w");
+    let group = |params: Vec<(String, VBox)>| {
+        ParamGroup::new(params.into_iter().map(|(_, param)| param).collect())
+    };
+
+    // grad_w = -28/3, grad_b = -4
+    let mut optimizer = SGD::new(
+        0.01,
+        vec![group(weights).lr(0.1).weight_decay(1.), group(biases)],
+    );
+    step(&model, &mut optimizer);
+    let params = model.get_params();
+    assert!((params[0].get_array().to_vec()[0] - (1. + 2.5 / 3.)).abs() < 1e-5);
+    assert!((params[1].get_array().to_vec()[0] - 0.04).abs() < 1e-6);
+
+    optimizer.set_lr(0.005);
+    let groups = optimizer.param_groups();
+    let options = groups.options(&groups.groups()[0]);
+    assert!((options.lr - 0.05).abs() < 1e-6);
+    assert_eq!(options.weight_decay, 1.);
+    assert_eq!(groups.options(&groups.groups()[1]).lr, 0.005);
+
+    let w = var!(array2!([[1.]]));
+    w.set_grad(array2!([[2.]]));
+    let mut optimizer = Momentum::new(0.1, 0.9, vec![w.clone()]);
+    optimizer.update();
+    assert!((w.get_array().to_vec()[0] - 0.8).abs() < 1e-6);
+}
+
+#[test]
+#[should_panic(expected = "more than once in the param groups")]
+fn param_groups_overlap() {
+    let w = var!(array2!([[1.]]));
+    SGD::new(
+        1.,
+        vec![
+            ParamGroup::new(vec![w.clone()]),
+            ParamGroup::new(vec![w.clone()]),
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "more than once in the param groups")]
+fn add_overlapping_param_group() {
+    let model = linear_model(1.);
+    let mut optimizer = SGD::new(1., model.clone());
+    optimizer.add_param_group(ParamGroup::new(model.get_params()));
+}
+
+#[test]
+fn lbfgs_rosenbrock() {
+    let rosenbrock = |x0: &VBox, x1: &VBox| 100 * (x1 - x0.powi(2)).powi(2) + (x0 - 1).powi(2);
+    for line_search in [LineSearch::StrongWolfe, LineSearch::Fixed] {
+        let x0 = var!(array0!(0.));
+        let x1 = var!(array0!(2.));
+        let lr = match line_search {
+            LineSearch::StrongWolfe => 1.,
+            LineSearch::Fixed => 0.1,
+        };
+        let mut optimizer = LBFGS::new(lr, vec![x0.clone(), x1.clone()])
+            .max_iter(100)
+            .history_size(10)
+            .line_search(line_search);
+        for _ in 0..10 {
+            optimizer.step(|| rosenbrock(x0, x1));
+        }
+        let (x0, x1) = (x0.get_array().to_vec()[0], x1.get_array().to_vec()[0]);
+        assert!(
+            (x0 - 1.).abs() < 1e-3 && (x1 - 1.).abs() < 1e-3,
+            "{:?}: ({}, {})",
+            line_search,
+            x0,
+            x1
+        );
+    }
+}
+
+#[test]
+fn lbfgs_model() {
+    let model = linear_model(0.);
+    let mut optimizer = LBFGS::new(1., model.clone());
+    let x = var!(array2!([[1.], [2.], [3.]]));
+    let t = var!(array2!([[2.], [4.], [6.]]));
+    let loss = optimizer.step(|| F::mean_squared_error(&model.call(x), t));
+    assert!((loss - 56. / 3.).abs() < 1e-4);
+    assert!((weight(&model) - 2.).abs() < 1e-4);
+}
+
+#[test]
+fn lbfgs_max_eval() {
+    let model = linear_model(0.);
+    let mut optimizer = LBFGS::new(0.01, model.clone())
+        .line_search(LineSearch::Fixed)
+        .max_eval(3)
+        .max_iter(100);
+    let x = var!(array2!([[1.], [2.], [3.]]));
+    let t = var!(array2!([[2.], [4.], [6.]]));
+    let mut calls = 0;
+    optimizer.step(|| {
+        calls += 1;
+        F::mean_squared_error(&model.call(x), t)
+    });
+    assert_eq!(calls, 3);
+}
+
+#[test]
+fn lbfgs_as_optimizer() {
+    // grad = -56/3 at w = 0, and the first step is lr * min(1, 1 / |grad|_1)
+    // along -grad, so it moves w by exactly lr.
+    let model = linear_model(0.);
+    let mut optimizer = LBFGS::new(0.5, model.clone());
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 0.5).abs() < 1e-6);
+
+    let model = linear_model(0.);
+    let mut optimizer = LBFGS::new(1., model.clone());
+    let mut scheduler = ExponentialLR::new(&mut optimizer, 0.5);
+    scheduler.step(&mut optimizer);
+    optimizer.add_hook(Box::new(ClipGradValue::new(0.5)));
+    step(&model, &mut optimizer);
+    assert!((weight(&model) - 0.25).abs() < 1e-6);
+}
+
+#[test]
+fn lbfgs_state_resume() {
+    let rosenbrock = |x0: &VBox, x1: &VBox| 100 * (x1 - x0.powi(2)).powi(2) + (x0 - 1).powi(2);
+    let lbfgs = |x0: &VBox, x1: &VBox| {
+        LBFGS::new(1., vec![x0.clone(), x1.clone()])
+            .max_iter(3)
+            .history_size(2)
+    };
+    let (x0, x1) = (var!(array0!(0.)), var!(array0!(2.)));
+    let mut original = lbfgs(x0, x1);
+    for _ in 0..3 {
+        original.step(|| rosenbrock(x0, x1));
+    }
+    let state = original.state_dict();
+    assert!(state.contains_key("history.1.s") && !state.contains_key("history.2.s"));
+
+    let (y0, y1) = (var!(x0.get_array()), var!(x1.get_array()));
+    let mut resumed = lbfgs(y0, y1);
+    resumed.load_state_dict(&state).unwrap();
+    for _ in 0..2 {
+        original.step(|| rosenbrock(x0, x1));
+        resumed.step(|| rosenbrock(y0, y1));
+    }
+    assert_eq!(x0.get_array(), y0.get_array());
+    assert_eq!(x1.get_array(), y1.get_array());
+
+    let mut bad = state.clone();
+    bad.insert("history.2.rho".to_string(), array0!(1.));
+    assert!(matches!(
+        resumed.load_state_dict(&bad),
+        Err(DezeroError::MissingKey(key)) if key == "history.2.s"
+    ));
+    let mut bad = state.clone();
+    bad.insert("history.3.s".to_string(), Array::zeros(&[2]));
+    assert!(matches!(
+        resumed.load_state_dict(&bad),
+        Err(DezeroError::UnexpectedKey(key)) if key == "history.3.s"
+    ));
+    let mut bad = state;
+    bad.insert("d".to_string(), Array::zeros(&[3]));
+    assert!(matches!(
+        resumed.load_state_dict(&bad),
+        Err(DezeroError::ParamShape { .. })
+    ));
+}
+
+#[test]
+fn lbfgs_update_one() {
+    let model = linear_model(0.);
+    let mut optimizer = LBFGS::new(0.5, model.clone());
+    let x = var!(array2!([[1.], [2.], [3.]]));
+    let t = var!(array2!([[2.], [4.], [6.]]));
+    F::mean_squared_error(&model.call(x), t).backward();
+    let options = optimizer
+        .param_groups()
+        .options(&optimizer.param_groups().groups()[0]);
+    optimizer.update_one(model.get_params()[0].clone(), &options);
+    assert!((weight(&model) - 0.5 * 56. / 3.).abs() < 1e-4);
+}
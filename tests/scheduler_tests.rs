@@ -0,0 +1,122 @@
+extern crate dezero;
+
+use dezero::{
+    layers::{Linear, Model},
+    optimizers::{Optimizer, SGD},
+    schedulers::{
+        CosineAnnealingWarmRestarts, ExponentialLR, LinearWarmup, LrScheduler, OneCycle,
+        ReduceLROnPlateau, StepLR,
+    },
+};
+
+fn sgd(lr: f32) -> SGD {
+    SGD::new(lr, Model::new(Linear::new(1, false)))
+}
+
+fn lrs(scheduler: &mut dyn LrScheduler, optimizer: &mut SGD, steps: usize) -> Vec<f32> {
+    (0..steps)
+        .map(|_| {
+            scheduler.step(optimizer);
+            optimizer.lr()
+        })
+        .collect()
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn step_and_exponential() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = StepLR::new(&mut optimizer, 2, 0.5);
+    assert_close(
+        &lrs(&mut scheduler, &mut optimizer, 5),
+        &[1., 0.5, 0.5, 0.25, 0.25],
+    );
+
+    let mut optimizer = sgd(1.);
+    let mut scheduler = ExponentialLR::new(&mut optimizer, 0.5);
+    assert_close(&lrs(&mut scheduler, &mut optimizer, 3), &[0.5, 0.25, 0.125]);
+}
+
+#[test]
+fn cosine_warm_restarts() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = CosineAnnealingWarmRestarts::new(&mut optimizer, 2, 2, 0.);
+    // Cycles of length 2 then 4.
+    assert_close(
+        &lrs(&mut scheduler, &mut optimizer, 6),
+        &[0.5, 1., 0.853_553_4, 0.5, 0.146_446_6, 1.],
+    );
+}
+
+#[test]
+#[should_panic(expected = "step_size must be positive")]
+fn step_lr_zero_step_size() {
+    StepLR::new(&mut sgd(1.), 0, 0.5);
+}
+
+#[test]
+#[should_panic(expected = "t_0 must be positive")]
+fn cosine_warm_restarts_zero_period() {
+    CosineAnnealingWarmRestarts::new(&mut sgd(1.), 0, 2, 0.);
+}
+
+#[test]
+#[should_panic(expected = "t_mult must be at least 1")]
+fn cosine_warm_restarts_zero_t_mult() {
+    CosineAnnealingWarmRestarts::new(&mut sgd(1.), 2, 0, 0.);
+}
+
+#[test]
+fn cosine_warm_restarts_constant_period() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = CosineAnnealingWarmRestarts::new(&mut optimizer, 2, 1, 0.);
+    assert_close(&lrs(&mut scheduler, &mut optimizer, 4), &[0.5, 1., 0.5, 1.]);
+}
+
+#[test]
+fn linear_warmup() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = LinearWarmup::new(&mut optimizer, 0.25, 3);
+    assert_eq!(optimizer.lr(), 0.25);
+    assert_close(
+        &lrs(&mut scheduler, &mut optimizer, 4),
+        &[0.5, 0.75, 1., 1.],
+    );
+}
+
+#[test]
+#[should_panic(expected = "warmup_steps must be positive")]
+fn linear_warmup_zero_steps() {
+    LinearWarmup::new(&mut sgd(1.), 0.25, 0);
+}
+
+#[test]
+fn reduce_on_plateau() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = ReduceLROnPlateau::new(&mut optimizer, 0.1, 1).min_lr(0.05);
+    let lrs = [1., 0.9, 0.9, 0.9, 0.5, 0.5, 0.5]
+        .into_iter()
+        .map(|loss| {
+            scheduler.step_with_metric(&mut optimizer, loss);
+            optimizer.lr()
+        })
+        .collect::<Vec<_>>();
+    assert_close(&lrs, &[1., 1., 1., 0.1, 0.1, 0.1, 0.05]);
+}
+
+#[test]
+fn one_cycle() {
+    let mut optimizer = sgd(1.);
+    let mut scheduler = OneCycle::with_options(&mut optimizer, 1., 4, 0.5, 10., 10.);
+    assert_eq!(optimizer.lr(), 0.1);
+    assert_close(
+        &lrs(&mut scheduler, &mut optimizer, 5),
+        &[0.55, 1., 0.505, 0.01, 0.01],
+    );
+}
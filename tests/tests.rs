@@ -1,7 +1,12 @@
 extern crate dezero;
 
 use dezero::functions::{self as F, mean_squared_error};
-use dezero::{array0, array1, array2, array_with_shape, scaler, var, variable::VBox};
+use dezero::layers::{Model, MLP};
+use dezero::{
+    array::Array, array0, array1, array2, array_with_shape, autograd, s, scaler, var,
+    variable::VBox, DezeroError,
+};
+use std::slice;
 
 macro_rules! square {
     ($x: expr) => {
@@ -150,25 +155,21 @@ fn rosenbrock_opt_test() {
     let x1 = scaler!(2);
 
     let lr = 0.001;
-    let max_iter = 100;
+    let max_iter = 10000;
 
     for _ in 0..max_iter {
-        println!("{}, {}", x0, x1);
-
         let y = rosenbrock(x0, x1);
 
-        println!("{}", y);
-
         x0.clear_grad();
         x1.clear_grad();
         y.backward();
 
         x0.set_array(x0.get_array() - lr * x0.get_grad());
-        x1.set_array(x0.get_array() - lr * x0.get_grad());
+        x1.set_array(x1.get_array() - lr * x1.get_grad());
     }
 
-    assert!(x0.get_grad().all_close(&array0!(1), 1e-8));
-    assert!(x1.get_grad().all_close(&array0!(1), 1e-8));
+    assert!(x0.get_array().all_close(&array0!(1), 2e-2));
+    assert!(x1.get_array().all_close(&array0!(1), 2e-2));
 }
 
 #[test]
@@ -256,3 +257,441 @@ fn backward_test() {
 
     // panic!()
 }
+
+#[test]
+fn cross_entropy_labels_test() {
+    let x = var!(array2!([[0.2, 0.3, 0.5], [0.6, 0.3, 0.1]]));
+    let labels = Array::new(vec![2i64, 0], vec![2]);
+    let loss = F::cross_entropy_loss_with_labels(x, &labels);
+    loss.backward();
+    assert!(loss
+        .get_array()
+        .all_close(&array0!(-(0.5f32.ln() + 0.6f32.ln())), 1e-6));
+    assert!(x
+        .get_grad()
+        .all_close(&array2!([[0., 0., -2.], [-1. / 0.6, 0., 0.]]), 1e-5));
+}
+
+#[test]
+#[should_panic(expected = "out of range")]
+fn cross_entropy_negative_label_test() {
+    let x = var!(array2!([[0.2, 0.8]]));
+    F::cross_entropy_loss_with_labels(x, &Array::new(vec![-1i64], vec![1]));
+}
+
+#[test]
+fn transpose_axes_test() {
+    let x = var!(array1!(0..24).reshape(&[2, 3, 4]));
+    let y = x.transpose_axes(&[1, 2, 0]);
+    let w = var!(array1!(0..24).reshape(&[3, 4, 2]));
+    let z = (&y * w).sum(None, false);
+    z.backward();
+
+    assert_eq!(y.get_shape(), vec![3, 4, 2]);
+    assert_eq!(x.get_grad(), w.get_array().transpose_axes(&[2, 0, 1]));
+}
+
+#[test]
+fn batched_matmul_backward_test() {
+    let x = var!(array1!(0..12).reshape(&[2, 2, 3]));
+    let w = var!(array1!(0..6).reshape(&[3, 2]));
+    let y = x.matmul(w);
+    y.sum(None, false).backward();
+
+    assert_eq!(x.get_grad(), array2!([[1, 5, 9]; 4]).reshape(&[2, 2, 3]));
+    assert_eq!(w.get_grad(), array2!([[18; 2], [22; 2], [26; 2]]));
+}
+
+#[test]
+fn get_item_test() {
+    let x = var!(array1!(0..6).reshape(&[2, 3]));
+    let y = x.get_item(&s![vec![0, 0, 1]]);
+    y.backward();
+
+    assert_eq!(y.get_array(), array2!([[0, 1, 2], [0, 1, 2], [3, 4, 5]]));
+    assert_eq!(x.get_grad(), array2!([[2; 3], [1; 3]]));
+
+    let x = var!(array1!(0..6).reshape(&[2, 3]));
+    let y = x.get_item(&s![1, 1..]);
+    y.backward();
+
+    assert_eq!(y.get_array(), array1!([4, 5]));
+    assert_eq!(x.get_grad(), array2!([[0, 0, 0], [0, 1, 1]]));
+}
+
+#[test]
+fn concat_split_test() {
+    let x0 = var!(array1!(0..6).reshape(&[2, 3]));
+    let x1 = var!(array1!(0..2).reshape(&[2, 1]));
+    let y = F::concat(&[x0.clone(), x1.clone()], 1);
+    let ys = F::split(&y, &[1, 3], 1);
+    let z = &ys[0] * 2. + ys[1].sum(None, false);
+    z.backward();
+
+    assert_eq!(ys[1].get_array(), array2!([[1, 2, 0], [4, 5, 1]]));
+    assert_eq!(x0.get_grad(), array2!([[2; 3]; 2]));
+    assert_eq!(x1.get_grad(), array2!([[2], [2]]));
+
+    let s = F::stack(&[x1.clone(), x1.clone()], 0);
+    assert_eq!(s.get_shape(), vec![2, 2, 1]);
+}
+
+#[test]
+fn reduction_backward_test() {
+    let x = var!(array2!([[1, 5, 2], [4, 3, 6]]));
+    let y = x.sum(Some(&[0]), false) + x.mean(Some(&[1]), true);
+    y.sum(None, false).backward();
+    assert_eq!(x.get_grad(), array2!([[3; 3]; 2]));
+
+    let x = var!(array2!([[1, 5, 5], [4, 3, 6]]));
+    let y = x.max(Some(&[1]), false) * var!(array1!([1, 2]));
+    y.sum(None, false).backward();
+    assert_eq!(y.get_array(), array1!([5, 12]));
+    assert_eq!(x.get_grad(), array2!([[0, 1, 0], [0, 0, 2]]));
+
+    let x = var!(array2!([[1, 5, 2], [4, 3, 6]]));
+    x.min(None, false).backward();
+    assert_eq!(x.get_grad(), array2!([[1, 0, 0], [0, 0, 0]]));
+}
+
+#[test]
+fn dangling_node_test() {
+    let weak = VBox::new(array0!(1)).downgrade();
+    assert!(matches!(weak.try_upgrade(), Err(DezeroError::DanglingNode)));
+}
+
+#[test]
+fn no_grad_test() {
+    let x = scaler!(2.);
+    {
+        let _guard = dezero::no_grad();
+        let y = x * x;
+        assert!(y.get_creator().is_none());
+        {
+            let _guard = dezero::using_config("enable_backprop", true);
+            assert!((x * x).get_creator().is_some());
+        }
+        assert!(!dezero::config::enable_backprop());
+    }
+    assert!(dezero::config::enable_backprop());
+    assert!((x * x).get_creator().is_some());
+
+    let no_grad = dezero::no_grad();
+    let test_mode = dezero::test_mode();
+    drop(no_grad);
+    assert!(dezero::config::enable_backprop());
+    assert!(!dezero::config::is_train());
+    drop(test_mode);
+    assert!(dezero::config::enable_backprop());
+    assert!(dezero::config::is_train());
+}
+
+#[test]
+fn dropout_test() {
+    let x = var!(array1!([1; 1000]));
+    let y = F::dropout(x, 0.5);
+    let kept = y
+        .get_array()
+        .gt(&array0!(0))
+        .astype::<f32>()
+        .sum(None, false);
+    assert!(y.get_array().get_data().iter().all(|&v| v == 0. || v == 2.));
+    assert!((kept.get_data()[0] - 500.).abs() < 100.);
+
+    let _guard = dezero::test_mode();
+    assert_eq!(F::dropout(x, 0.5).get_array(), x.get_array());
+}
+
+#[test]
+fn higher_order_test() {
+    let x = scaler!(2.);
+    let y = x.powi(4) - 2. * x.powi(2);
+    y.backward_with_options(false, true);
+    assert_eq!(x.get_grad(), array0!(24.));
+
+    let gx = x.get_grad_var();
+    x.clear_grad();
+    gx.backward();
+    assert_eq!(x.get_grad(), array0!(44.));
+}
+
+#[test]
+fn newton_method_test() {
+    let x = scaler!(2.);
+    for _ in 0..10 {
+        let y = x.powi(4) - 2. * x.powi(2);
+        x.clear_grad();
+        y.backward_with_options(false, true);
+        let gx = x.get_grad_var();
+        x.clear_grad();
+        gx.backward();
+        let gx2 = x.get_grad();
+        x.set_array(x.get_array() - gx.get_array() / gx2);
+    }
+    assert_eq!(x.get_array(), array0!(1.));
+}
+
+#[test]
+fn sin_higher_order_test() {
+    let x = scaler!(1.);
+    let y = x.sin();
+    y.backward_with_options(false, true);
+    for _ in 0..3 {
+        let gx = x.get_grad_var();
+        x.clear_grad();
+        gx.backward_with_options(false, true);
+    }
+    assert!(x.get_grad().all_close(&array0!(1f32.sin()), 1e-6));
+}
+
+#[test]
+fn autograd_grad_test() {
+    let x = scaler!(2.);
+    let y = x.powi(3);
+    let gx = autograd::grad(slice::from_ref(&y), slice::from_ref(x), None, true);
+    assert_eq!(gx[0].get_array(), array0!(12.));
+    assert!(x.get_option_grad().is_none());
+
+    let ggx = autograd::grad(&gx, slice::from_ref(x), None, false);
+    assert_eq!(ggx[0].get_array(), array0!(12.));
+
+    let unused = scaler!(1.);
+    let g = autograd::grad(&[y], slice::from_ref(unused), None, false);
+    assert_eq!(g[0].get_array(), array0!(0.));
+}
+
+#[test]
+fn jacobian_hessian_test() {
+    let x = var!(array1!([1., 2.]));
+    let y = x * x;
+    let jac = autograd::jacobian(&y, x, false);
+    assert_eq!(jac.get_array(), array2!([[2., 0.], [0., 4.]]));
+
+    let empty = x.get_item(&s![2..]) * x.get_item(&s![2..]);
+    let jac = autograd::jacobian(&empty, x, false);
+    assert_eq!(jac.get_shape(), vec![0, 2]);
+
+    let z = (x.powi(2) * x.get_item(&s![1])).sum(None, false);
+    let hes = autograd::hessian(&z, x);
+    assert_eq!(hes.get_array(), array2!([[4., 2.], [2., 12.]]));
+}
+
+#[test]
+fn jvp_test() {
+    let f = |x: &[VBox]| (&x[0] * &x[1]).sin() + x[0].exp();
+    let (y, ty) = autograd::jvp(f, &[array0!(0.), array0!(2.)], &[array0!(1.), array0!(0.)]);
+    assert_eq!(y, array0!(1.));
+    assert_eq!(ty, array0!(3.));
+
+    let w = array2!([[1., 2.], [3., 4.]]);
+    let f = |x: &[VBox]| F::softmax(&x[0].matmul(var!(w.clone())), 1);
+    let x = array2!([[1., -1.]]);
+    let t = array2!([[0.5, 0.25]]);
+    let (_, ty) = autograd::jvp(f, slice::from_ref(&x), slice::from_ref(&t));
+
+    let xv = var!(x);
+    let y = f(slice::from_ref(xv));
+    let jac = autograd::jacobian(&y, xv, false).get_array();
+    let expected = jac
+        .reshape(&[2, 2])
+        .matmul(&t.reshape(&[2, 1]))
+        .reshape(&[1, 2]);
+    assert!(ty.all_close(&expected, 1e-6));
+
+    let f = |x: &[VBox]| F::cross_entropy_loss(&x[0], &x[1]);
+    let primals = [
+        array2!([[0.2, 0.3, 0.5], [0.6, 0.3, 0.1]]),
+        array2!([[0., 0., 1.], [1., 0., 0.]]),
+    ];
+    let tangents = [
+        array2!([[1., 2., 3.], [4., 5., 6.]]),
+        array2!([[0.5, 0., 0.], [0., 0., 0.5]]),
+    ];
+    let (y, ty) = autograd::jvp(f, &primals, &tangents);
+    let x = primals.iter().cloned().map(VBox::new).collect::<Vec<_>>();
+    let g = autograd::grad(&[f(&x)], &x, None, false);
+    let expected = g
+        .iter()
+        .zip(&tangents)
+        .map(|(g, t)| (g.get_array() * t).sum(None, false))
+        .fold(array0!(0.), |acc, x| acc + x);
+    assert!(y.all_close(&array0!(-(0.5f32.ln() + 0.6f32.ln())), 1e-6));
+    assert!(ty.all_close(&expected, 1e-5));
+}
+
+#[test]
+fn dot_graph_test() {
+    let x0 = var!(array1!([1., 2., 3.]));
+    let x1 = scaler!(2.);
+    let y = (x0 * x1).exp() + x0;
+    let txt = y.to_dot();
+
+    assert!(txt.starts_with("digraph g {\n"));
+    assert!(txt.contains("label=\"Mul\\ngen 0\""));
+    assert!(txt.contains("label=\"Add\\ngen 2\""));
+    assert!(txt.contains(&format!("{} -> {}", x0.id(), y.get_creator().unwrap().id())));
+    assert!(txt.contains("label=\"[3]\""));
+    assert_eq!(txt.matches(" -> ").count(), 8);
+
+    let path = std::env::temp_dir().join("dezero_dot_graph_test.dot");
+    y.plot_dot_graph(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), txt);
+}
+
+#[test]
+fn named_variable_test() {
+    let x = VBox::new(array1!([1., 2.])).with_name("x");
+    assert_eq!(x.get_name().as_deref(), Some("x"));
+    assert!(format!("{}", x).contains("name: x"));
+
+    let y = x.exp();
+    assert_eq!(y.get_name(), None);
+    y.set_name("y");
+    assert!(y.to_dot().contains("label=\"x\\n[2]\""));
+    assert!(y.to_dot().contains("label=\"y\\n[2]\""));
+}
+
+#[test]
+fn named_params_test() {
+    let model = Model::new(MLP::new(&[4, 1], Box::new(F::sigmoid)));
+    model.call(var!(array2!([[1., 2.], [3., 4.]])));
+
+    let names = model
+        .named_params()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["layers.0.w", "layers.0.b", "layers.1.w", "layers.1.b"]
+    );
+    assert!(model.named_params()[2].1 == model.get_params()[2]);
+    assert_eq!(model.named_params()[2].1.get_shape(), vec![4, 1]);
+}
+
+#[test]
+fn state_dict_test() {
+    let model = Model::new(MLP::new(&[3, 2], Box::new(F::sigmoid)));
+    let x = var!(array2!([[1., 2.], [3., 4.]]));
+    let y = model.call(x).get_array();
+
+    let path = std::env::temp_dir().join("dezero_state_dict_test.bin");
+    model.save_weights(&path).unwrap();
+
+    let restored = Model::new(MLP::new(&[3, 2], Box::new(F::sigmoid)));
+    restored.load_weights(&path).unwrap();
+    assert_eq!(restored.call(x).get_array(), y);
+
+    let mut state_dict = model.state_dict();
+    state_dict.remove("layers.1.b");
+    assert!(matches!(
+        restored.load_state_dict(&state_dict),
+        Err(DezeroError::MissingKey(key)) if key == "layers.1.b"
+    ));
+
+    // Weights are created lazily, so a fresh model must still ask for them.
+    let mut state_dict = model.state_dict();
+    state_dict.remove("layers.0.w");
+    let fresh = Model::new(MLP::new(&[3, 2], Box::new(F::sigmoid)));
+    assert!(matches!(
+        fresh.load_state_dict(&state_dict),
+        Err(DezeroError::MissingKey(key)) if key == "layers.0.w"
+    ));
+
+    let mut state_dict = model.state_dict();
+    state_dict.insert("layers.0.w".to_string(), array2!([[1., 2.]]));
+    assert!(matches!(
+        restored.load_state_dict(&state_dict),
+        Err(DezeroError::ParamShape { key, .. }) if key == "layers.0.w"
+    ));
+
+    let mut state_dict = model.state_dict();
+    state_dict.insert("layers.2.w".to_string(), array2!([[1., 2.]]));
+    assert!(matches!(
+        restored.load_state_dict(&state_dict),
+        Err(DezeroError::UnexpectedKey(key)) if key == "layers.2.w"
+    ));
+
+    // A failed load must not overwrite any param, even those checked earlier.
+    let original = restored.state_dict();
+    let mut state_dict = model.state_dict();
+    for array in state_dict.values_mut() {
+        *array = Array::zeros(array.get_shape());
+    }
+    state_dict.insert("layers.1.w".to_string(), array2!([[1., 2.]]));
+    assert!(restored.load_state_dict(&state_dict).is_err());
+    state_dict.insert("layers.1.w".to_string(), Array::zeros(&[3, 2]));
+    state_dict.insert("layers.9.b".to_string(), Array::zeros(&[2]));
+    assert!(restored.load_state_dict(&state_dict).is_err());
+    assert_eq!(restored.state_dict(), original);
+
+    std::fs::write(&path, b"nope").unwrap();
+    assert!(matches!(
+        restored.load_weights(&path),
+        Err(DezeroError::Format(_))
+    ));
+}
+
+#[test]
+fn safetensors_test() {
+    let model = Model::new(MLP::new(&[3, 2], Box::new(F::sigmoid)));
+    let x = var!(array2!([[1., 2.], [3., 4.]]));
+    let y = model.call(x).get_array();
+
+    let path = std::env::temp_dir().join("dezero_safetensors_test.safetensors");
+    model.save_safetensors(&path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    assert_eq!(header_len % 8, 0);
+    assert!(std::str::from_utf8(&bytes[8..8 + header_len])
+        .unwrap()
+        .starts_with("{\"layers.0.b\":{\"dtype\":\"F32\",\"shape\":[3],\"data_offsets\":[0,12]}"));
+
+    let restored = Model::new(MLP::new(&[3, 2], Box::new(F::sigmoid)));
+    restored.load_safetensors(&path).unwrap();
+    assert_eq!(restored.call(x).get_array(), y);
+}
+
+#[test]
+fn torch_safetensors_test() {
+    // nn.Sequential(nn.Linear(2, 3), nn.ReLU(), nn.Linear(3, 1)) as written by safetensors.torch
+    let tensors: [(&str, Vec<usize>, Vec<f32>); 4] = [
+        ("0.bias", vec![3], vec![0.1, 0.2, 0.3]),
+        ("0.weight", vec![3, 2], vec![1., 2., 3., 4., 5., 6.]),
+        ("2.bias", vec![1], vec![-1.]),
+        ("2.weight", vec![1, 3], vec![0.5, -0.5, 1.]),
+    ];
+    let mut entries = vec!["\"__metadata__\":{\"format\":\"pt\"}".to_string()];
+    let mut data = Vec::new();
+    for (name, shape, values) in &tensors {
+        let begin = data.len();
+        for v in values {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        entries.push(format!(
+            "\"{}\":{{\"dtype\":\"F32\",\"shape\":{:?},\"data_offsets\":[{},{}]}}",
+            name,
+            shape,
+            begin,
+            data.len()
+        ));
+    }
+    let header = format!("{{{}}}   ", entries.join(","));
+    let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(&data);
+
+    let path = std::env::temp_dir().join("dezero_torch_test.safetensors");
+    std::fs::write(&path, bytes).unwrap();
+
+    let model = Model::new(MLP::new(&[3, 1], Box::new(F::relu)));
+    model.load_safetensors(&path).unwrap();
+    assert_eq!(model.named_params()[0].1.get_shape(), vec![2, 3]);
+
+    let y = model.call(var!(array2!([[1., -1.]])));
+    // hidden = relu([-1, -1, -1] + [0.1, 0.2, 0.3]) = 0
+    assert_eq!(y.get_array(), array2!([[-1.]]));
+    let y = model.call(var!(array2!([[1., 1.]])));
+    // hidden = [3.1, 7.2, 11.3]
+    assert!((y.get_array().to_vec()[0] - 8.25).abs() < 1e-5);
+}